@@ -0,0 +1,134 @@
+//! Derive macros of [odtgen](https://crates.io/crates/odtgen), enabled by its `derive` feature
+//!
+//! ```
+//! use odtgen::prelude::*;
+//! use chrono::NaiveDate;
+//!
+//! #[derive(OdtTableRow)]
+//! struct Sale {
+//!     #[odt(header = "Date", width = "3cm")]
+//!     date: NaiveDate,
+//!     product: String,
+//!     #[odt(column_style = "Amounts")]
+//!     amount: f64,
+//!     #[odt(skip)]
+//!     internal_id: u32,
+//! }
+//!
+//! let sales = vec![
+//!     Sale { date: NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(), product: "Apples".to_string(), amount: 12.5, internal_id: 1 },
+//! ];
+//! let table = Table::from_records(&sales);
+//! assert_eq!(Sale::headers(), vec!["Date", "product", "amount"]);
+//! assert_eq!(table.column_count(), 3);
+//! assert_eq!(table.columns[0].width.as_deref(), Some("3cm"));
+//! assert_eq!(table.rows[0].cells[2].value, Some(CellValue::Float(12.5)));
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, LitStr};
+
+/// Implement `odtgen::table_import::OdtTableRow` for a struct with named fields.
+///
+/// Each field is a column, converted with `odtgen::table_import::ToTableCell`.
+#[proc_macro_derive(OdtTableRow, attributes(odt))]
+pub fn derive_odt_table_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Options of a field (`#[odt(...)]` attributes)
+#[derive(Default)]
+struct FieldOptions {
+    header: Option<LitStr>,
+    column_style: Option<LitStr>,
+    width: Option<LitStr>,
+    skip: bool,
+}
+
+impl FieldOptions {
+    fn parse(field: &Field) -> syn::Result<Self> {
+        let mut options = FieldOptions::default();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("odt")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("header") {
+                    options.header = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("column_style") {
+                    options.column_style = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("width") {
+                    options.width = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                } else {
+                    return Err(meta.error("unsupported odt attribute (expected header, column_style, width or skip)"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "OdtTableRow requires a struct with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "OdtTableRow can only be derived for structs")),
+    };
+
+    let mut headers = Vec::new();
+    let mut columns = Vec::new();
+    let mut cells = Vec::new();
+    for field in fields {
+        let options = FieldOptions::parse(field)?;
+        if options.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let header = match options.header {
+            Some(header) => header.value(),
+            None => ident.to_string().trim_start_matches("r#").to_string(),
+        };
+        headers.push(header);
+        let style_name = optional_string(options.column_style);
+        let width = optional_string(options.width);
+        columns.push(quote! {{
+            let mut column = ::odtgen::table::TableColumn::new();
+            column.style_name = #style_name;
+            column.width = #width;
+            column
+        }});
+        cells.push(quote! {
+            ::odtgen::table_import::ToTableCell::to_table_cell(&self.#ident)
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::odtgen::table_import::OdtTableRow for #name #type_generics #where_clause {
+            fn headers() -> ::std::vec::Vec<::std::string::String> {
+                ::std::vec![#(::std::string::String::from(#headers)),*]
+            }
+
+            fn columns() -> ::std::vec::Vec<::odtgen::table::TableColumn> {
+                ::std::vec![#(#columns),*]
+            }
+
+            fn cells(&self) -> ::std::vec::Vec<::odtgen::table::TableCell> {
+                ::std::vec![#(#cells),*]
+            }
+        }
+    })
+}
+
+fn optional_string(value: Option<LitStr>) -> TokenStream2 {
+    match value {
+        Some(value) => quote!(::std::option::Option::Some(::std::string::String::from(#value))),
+        None => quote!(::std::option::Option::None),
+    }
+}
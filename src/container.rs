@@ -1,5 +1,9 @@
 use std::io::Write;
+use crate::error::Result;
 use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::note::NoteNumbering;
+use crate::statistics::StatisticsWalker;
+use crate::stylesheet::Stylesheet;
 
 /// Base struct representing things that can have children
 pub struct Container {
@@ -20,16 +24,54 @@ impl Container {
     pub fn len(&self) -> usize {
         self.children.len()
     }
+
+    /// Check whether the container has no children.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Check whether all children are blocks (see `FlatOdtXmlWrite::is_block`).
+    pub fn contains_only_blocks(&self) -> bool {
+        self.children.iter().all(|c| c.is_block())
+    }
+}
+
+impl Default for Container {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FlatOdtXmlWrite for Container {
     fn write_flat_odt_xml(
         &self,
         writer: &mut xml::writer::EventWriter<&mut dyn Write>,
-    ) -> xml::writer::Result<()> {
+    ) -> Result<()> {
         for c in &self.children {
             c.write_flat_odt_xml(writer)?;
         }
         Ok(())
     }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        for c in &self.children {
+            c.update_statistics(statistics);
+        }
+    }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        for c in &mut self.children {
+            c.number_notes(numbering);
+        }
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
+        for c in &self.children {
+            c.register_automatic_styles(stylesheet);
+        }
+    }
+
+    fn is_block(&self) -> bool {
+        self.contains_only_blocks()
+    }
 }
@@ -1,20 +1,78 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
 use crate::container::Container;
+use crate::error::{Error, Result};
+use xml::attribute::OwnedAttribute;
+use xml::name::OwnedName;
+use xml::namespace::Namespace;
 use xml::reader::{EventReader, XmlEvent as REvent, ParserConfig};
 use xml::writer::{EventWriter, XmlEvent as WEvent, EmitterConfig};
+use crate::fodt_parser::{root_namespace, FodtParser, STYLE_NS, TEXT_NS};
 use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::master_page::{write_header_footer, MasterPage};
+use crate::merge::MergeData;
+use crate::metadata::Metadata;
+use crate::note::{NoteClass, NoteNumbering, NotesConfiguration};
+use crate::package;
+use crate::page_layout::PageLayout;
 use crate::paragraph::Paragraph;
-use crate::stylesheet::Stylesheet;
+use crate::raw_xml::RawXml;
+use serde::Serialize;
+use crate::statistics::{DocumentStatistics, StatisticsWalker};
+use crate::style::{Style, StyleFamily};
+use crate::stylesheet::{style_key, Stylesheet};
 
-const BLANK_FODT: &str = include_str!("../assets/blank.fodt");
+pub(crate) const BLANK_FODT: &str = include_str!("../assets/blank.fodt");
+
+/// Markers of the template replaced by the document sections, with the element receiving
+/// the section when the marker is absent.
+const SECTION_MARKERS: [(&str, &str); 6] = [
+    ("__META__", "office:meta"),
+    ("__STYLES__", "office:styles"),
+    ("__NOTES_CONFIGURATION__", "office:styles"),
+    ("__AUTOMATIC_STYLES__", "office:automatic-styles"),
+    ("__MASTER_PAGES__", "office:master-styles"),
+    ("__BODY__", "office:text"),
+];
 
 /// The main structure of the document tree
 pub struct Document {
+    pub metadata: Metadata,
     pub stylesheet: Stylesheet,
+    /// Name of the page layout used by the standard master page
+    pub page_layout_name: String,
+    /// Page header (all pages, or right pages when `header_left` is used)
     pub header: Container,
-    pub body: Container
+    /// Page header for left (even) pages
+    pub header_left: Container,
+    /// Page header for the first page
+    pub header_first: Container,
+    /// Page footer (all pages, or right pages when `footer_left` is used)
+    pub footer: Container,
+    /// Page footer for left (even) pages
+    pub footer_left: Container,
+    /// Page footer for the first page
+    pub footer_first: Container,
+    /// Additional master pages (besides `Standard`)
+    pub master_pages: Vec<MasterPage>,
+    pub footnotes_configuration: NotesConfiguration,
+    pub endnotes_configuration: NotesConfiguration,
+    pub body: Container,
+    /// Flat ODT the document is generated from
+    template: Cow<'static, str>,
+    /// Whether the styles of the template are replaced by the ones of the stylesheet with the
+    /// same name, as the stylesheet includes them (custom templates and parsed documents)
+    pub(crate) override_template_styles: bool,
+    /// Elements of a parsed document replacing the ones of the template (font face
+    /// declarations, settings and scripts)
+    pub(crate) template_sections: Vec<RawXml>,
+    /// Content of the `<!-- __SLOT:name__ -->` markers of the template
+    slots: Vec<(String, Container)>,
+    /// Values of the `{{field}}` placeholders
+    merge_data: Option<MergeData>,
 }
 
 impl Document {
@@ -22,104 +80,601 @@ impl Document {
     /// Create a new blank document with the default stylesheet
     pub fn new() -> Self {
         Document {
+            metadata: Metadata::new(),
             stylesheet: Stylesheet::default(),
+            page_layout_name: "pm1".to_string(),
             header: Container::new(),
-            body: Container::new()
+            header_left: Container::new(),
+            header_first: Container::new(),
+            footer: Container::new(),
+            footer_left: Container::new(),
+            footer_first: Container::new(),
+            master_pages: Vec::new(),
+            footnotes_configuration: NotesConfiguration::new(NoteClass::Footnote),
+            endnotes_configuration: NotesConfiguration::new(NoteClass::Endnote),
+            body: Container::new(),
+            template: Cow::Borrowed(BLANK_FODT),
+            override_template_styles: false,
+            template_sections: Vec::new(),
+            slots: Vec::new(),
+            merge_data: None,
+        }
+    }
+
+    /// Create a new document based on a template file: a Flat ODT (`.fodt`) or an ODT package
+    /// (`.ott`, `.odt`).
+    ///
+    /// See `with_template_str`.
+    pub fn with_template<P: AsRef<Path>>(path: P) -> Result<Document> {
+        let bytes = std::fs::read(path)?;
+        let template = if bytes.starts_with(b"PK") {
+            package::read_flat(&bytes)?
+        } else {
+            String::from_utf8(bytes).map_err(|e| Error::Template(e.to_string()))?
+        };
+        Document::with_template_str(template)
+    }
+
+    /// Create a new document based on a Flat ODT template.
+    ///
+    /// The styles and master pages of the template are loaded into the document (the default
+    /// styles are kept unless overridden), and its body is kept as is. The document body is
+    /// written at the `<!-- __BODY__ -->` marker, or at the end of the template body.
+    /// Markers in the form `<!-- __SLOT:name__ -->` are replaced by the content of the
+    /// corresponding slot (see `slot_mut`).
+    pub fn with_template_str<T: Into<String>>(template: T) -> Result<Document> {
+        let template = template.into();
+        let mut doc = FodtParser::parse_template(&template)?;
+        doc.slots = Document::template_markers(&template)?.into_iter()
+            .filter_map(|marker| marker.strip_prefix("__SLOT:")?.strip_suffix("__").map(str::to_string))
+            .map(|name| (name, Container::new()))
+            .collect();
+        doc.template = Cow::Owned(template);
+        doc.override_template_styles = true;
+        Ok(doc)
+    }
+
+    /// Markers (comments) found in a template
+    fn template_markers(template: &str) -> Result<Vec<String>> {
+        let mut reader = EventReader::new_with_config(
+            template.as_bytes(),
+            ParserConfig::new().ignore_comments(false)
+        );
+        let mut markers = Vec::new();
+        loop {
+            match reader.next()? {
+                REvent::Comment(text) => markers.push(text.trim().to_string()),
+                REvent::EndDocument => return Ok(markers),
+                _ => {}
+            }
         }
     }
 
-    /// Copy the XML structure until a marker (in the form of a comment) is found.
+    /// Get the content of a slot of the template.
+    ///
+    /// Fails if the template has no `<!-- __SLOT:name__ -->` marker.
+    pub fn slot_mut(&mut self, name: &str) -> Result<&mut Container> {
+        match self.slots.iter_mut().find(|(slot_name, _)| slot_name == name) {
+            Some((_, content)) => Ok(content),
+            None => Err(Error::Template(format!(
+                "Slot '{}' not found in the template (expected a <!-- __SLOT:{}__ --> marker)!", name, name
+            ))),
+        }
+    }
+
+    /// Replace the content of a slot of the template.
+    pub fn set_slot(&mut self, name: &str, content: Container) -> Result<()> {
+        *self.slot_mut(name)? = content;
+        Ok(())
+    }
+
+    /// Read a Flat ODT document (e.g. one saved by LibreOffice).
+    ///
+    /// Metadata, styles, master pages and the body are parsed into the document tree.
+    /// Elements without a dedicated type are kept as `RawXml` nodes.
+    pub fn from_fodt<R: std::io::Read>(reader: R) -> Result<Document> {
+        FodtParser::parse(reader)
+    }
+
+    /// Key of a template element, compared with the ones of the stylesheet.
+    fn template_style_key(name: &OwnedName, attributes: &[OwnedAttribute]) -> String {
+        let attribute = |namespace_uri: &str, local_name: &str| attributes.iter()
+            .find(|a| a.name.namespace.as_deref() == Some(namespace_uri) && a.name.local_name == local_name)
+            .map(|a| a.value.as_str());
+        let tag_name = name.borrow().repr_display().to_string();
+        if tag_name == "text:outline-style" {
+            return style_key(&tag_name, None, None);
+        }
+        let family = attribute(STYLE_NS, "family").or(attribute(TEXT_NS, "note-class"));
+        style_key(&tag_name, attribute(STYLE_NS, "name"), family)
+    }
+
+    /// Keys (see `stylesheet::style_key`) of the template elements replaced by the document.
+    fn overridden_template_elements(&self) -> HashSet<String> {
+        let mut keys = if self.override_template_styles {
+            self.stylesheet.style_keys()
+        } else {
+            HashSet::new()
+        };
+        for section in &self.template_sections {
+            keys.insert(style_key(&section.name(), None, None));
+        }
+        let master_pages = std::iter::once("Standard").chain(self.master_pages.iter().map(|m| m.name()));
+        keys.extend(master_pages.map(|name| style_key("style:master-page", Some(name), None)));
+        for note_class in [NoteClass::Footnote, NoteClass::Endnote] {
+            keys.insert(style_key("text:notes-configuration", None, Some(note_class.as_ref())));
+        }
+        keys
+    }
+
+    /// Set the page layout of the document, adding it to the stylesheet.
+    pub fn set_page_layout(&mut self, page_layout: PageLayout) {
+        self.page_layout_name = page_layout.name().to_string();
+        self.stylesheet.add_page_layout(page_layout);
+    }
+
+    /// Add a master page to the document, replacing any master page with the same name.
+    ///
+    /// Fails for `Standard`, which is defined by the fields of the document (`page_layout_name`,
+    /// `header`, `footer`...).
+    pub fn add_master_page(&mut self, master_page: MasterPage) -> Result<()> {
+        if master_page.name() == "Standard" {
+            return Err(Error::stylesheet(
+                "Standard", "style:name", "The Standard master page is defined by the document fields!"
+            ));
+        }
+        self.master_pages.retain(|m| m.name() != master_page.name());
+        self.master_pages.push(master_page);
+        Ok(())
+    }
+
+    /// Get an additional master page by name.
+    pub fn master_page_mut(&mut self, name: &str) -> Option<&mut MasterPage> {
+        self.master_pages.iter_mut().find(|m| m.name() == name)
+    }
+
+    /// Create (or reuse) an automatic paragraph style that starts a new page using the given
+    /// master page. Returns the name of the style, to be used by the first paragraph of that page.
     ///
     /// # Arguments
     ///
-    /// * `reader`: The XML reader pointing to the "next" event.
-    /// * `writer`: An XML writer
-    /// * `marker`: The marker which will make the function returns.
+    /// * `master_page_name`: `Standard` or the name of one of `master_pages` (fails otherwise).
+    /// * `parent_style_name`: The paragraph style the new style is based on.
+    pub fn master_page_style(&mut self, master_page_name: &str, parent_style_name: &str) -> Result<String> {
+        if master_page_name != "Standard" && !self.master_pages.iter().any(|m| m.name() == master_page_name) {
+            return Err(Error::stylesheet(master_page_name, "master-page-name", "Master page not found!"));
+        }
+        let existing = self.stylesheet.styles().find(|s| {
+            s.automatic
+                && s.family == StyleFamily::Paragraph
+                && s.master_page_name.as_deref() == Some(master_page_name)
+                && s.parent_style_name.as_deref() == Some(parent_style_name)
+        });
+        if let Some(style) = existing {
+            return Ok(style.name().to_string());
+        }
+        //Names made readable by replacing special characters, so they may need a suffix
+        let base_name: String = format!("MP_{}_{}", master_page_name, parent_style_name)
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
+            .collect();
+        let mut style_name = base_name.clone();
+        let mut suffix = 1;
+        while self.stylesheet.contains(&style_name) {
+            suffix += 1;
+            style_name = format!("{}_{}", base_name, suffix);
+        }
+        let mut style = Style::new(style_name.clone(), StyleFamily::Paragraph);
+        style.automatic = true;
+        style.parent_style_name = Some(parent_style_name.to_string());
+        style.master_page_name = Some(master_page_name.to_string());
+        self.stylesheet.add_style(style);
+        Ok(style_name)
+    }
+
+    /// Add the automatic styles needed by an element (e.g. the widths of table columns).
     ///
-    /// returns: Result<(), String>
-    fn copy_until_marker<R, W>(
-        reader: &mut EventReader<R>,
-        writer: &mut EventWriter<W>,
-        marker: &str,
-    ) -> Result<(), String>
-    where
-        R: std::io::Read,
-        W: std::io::Write,
-    {
-        loop {
-            let ev = reader.next().map_err(|e| e.to_string())?;
+    /// Done when generating the document for its body, headers, footers and slots; only needed
+    /// for the elements written by a `DocumentWriter`, before creating it.
+    pub fn register_automatic_styles<T: FlatOdtXmlWrite + ?Sized>(&mut self, element: &T) {
+        element.register_automatic_styles(&mut self.stylesheet);
+    }
 
-            match ev {
-                REvent::EndDocument => panic!("Document end found!"),
-                REvent::Comment(text) => {
-                    if (&text).trim() == marker {
-                        break
-                    }
-                }
-                other => {
-                    let ev = other.as_writer_event().ok_or("Falha ao construir XmlEvent de escrita!")?;
-                    writer.write(ev).map_err(|e| e.to_string())?;
+    /// Add the automatic styles needed by the content of the document.
+    pub(crate) fn register_content_styles(&mut self) {
+        let stylesheet = &mut self.stylesheet;
+        let headers_footers = [&self.header, &self.header_left, &self.header_first, &self.footer, &self.footer_left, &self.footer_first];
+        for content in headers_footers {
+            content.register_automatic_styles(stylesheet);
+        }
+        for m in &self.master_pages {
+            for content in [&m.header, &m.header_left, &m.header_first, &m.footer, &m.footer_left, &m.footer_first] {
+                content.register_automatic_styles(stylesheet);
+            }
+        }
+        for (_, content) in &self.slots {
+            content.register_automatic_styles(stylesheet);
+        }
+        self.body.register_automatic_styles(stylesheet);
+    }
+
+    /// Number the notes of the body and slots, in the order of their markers in the template.
+    ///
+    /// Only the sections preceding the body (included) are numbered if `before_body` is true,
+    /// the other ones otherwise.
+    pub(crate) fn number_notes(&mut self, numbering: &mut NoteNumbering, before_body: bool) -> Result<()> {
+        let mut in_range = before_body;
+        for marker in Document::template_markers(&self.template)? {
+            if marker == "__BODY__" {
+                if in_range {
+                    self.body.number_notes(numbering);
                 }
+                in_range = !before_body;
+                continue;
+            }
+            let slot = marker.strip_prefix("__SLOT:").and_then(|m| m.strip_suffix("__"));
+            if in_range && let Some(slot) = slot
+                && let Some((_, content)) = self.slots.iter_mut().find(|(name, _)| name == slot) {
+                content.number_notes(numbering);
             }
         }
         Ok(())
     }
 
-    /// Write the header of the document
-    fn write_header(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
-        if self.header.len() > 0 {
-            writer.write(WEvent::start_element("style:header"))?;
-            self.header.write_flat_odt_xml(writer)?;
-            writer.write(WEvent::end_element())?;
+    /// Numbering of the notes of the document, starting at its first note
+    pub(crate) fn note_numbering(&self) -> NoteNumbering {
+        NoteNumbering::new(&self.footnotes_configuration, &self.endnotes_configuration)
+    }
+
+    /// Write the master page(s) of the document
+    fn write_master_pages(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let layout_names = std::iter::once(self.page_layout_name.as_str())
+            .chain(self.master_pages.iter().map(|m| m.page_layout_name.as_str()));
+        for layout_name in layout_names {
+            if self.stylesheet.page_layout(layout_name).is_none() {
+                return Err(Error::stylesheet(layout_name, "page-layout-name", "Page layout not found!"));
+            }
+        }
+        writer.write(
+            WEvent::start_element("style:master-page")
+                .attr("style:name", "Standard")
+                .attr("style:page-layout-name", &self.page_layout_name)
+        )?;
+        self.write_header(writer)?;
+        self.write_footer(writer)?;
+        writer.write(WEvent::end_element())?;
+        for master_page in &self.master_pages {
+            master_page.write_flat_odt_xml(writer)?;
         }
         Ok(())
     }
 
-    pub fn save(&mut self, output: &PathBuf) -> Result<(), String> {
-        let mut file = File::create(output).map_err(|e| e.to_string())?;
-        self.generate_fodt(&mut file)?;
-        Ok(())
+    /// Write the headers of the document
+    fn write_header(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        write_header_footer(writer, "style:header", &self.header)?;
+        write_header_footer(writer, "style:header-left", &self.header_left)?;
+        write_header_footer(writer, "style:header-first", &self.header_first)
     }
 
-    /// Export the document as Flat ODT (single XML)
-    pub fn generate_fodt(&mut self, out: &mut dyn Write) -> Result<(), String> {
-        let mut reader = EventReader::new_with_config(
-            BLANK_FODT.as_bytes(),
-            ParserConfig::new()
-                .ignore_comments(false)
-        );
+    /// Write the footers of the document
+    fn write_footer(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        write_header_footer(writer, "style:footer", &self.footer)?;
+        write_header_footer(writer, "style:footer-left", &self.footer_left)?;
+        write_header_footer(writer, "style:footer-first", &self.footer_first)
+    }
 
-        let mut writer = EmitterConfig::new()
-            .perform_indent(true)
-            .create_writer(out);
+    /// Compute the statistics (paragraphs, words, tables...) of the document body and slots.
+    pub fn statistics(&self) -> DocumentStatistics {
+        let mut statistics = StatisticsWalker::default();
+        self.body.update_statistics(&mut statistics);
+        for (_, content) in &self.slots {
+            content.update_statistics(&mut statistics);
+        }
+        statistics.statistics()
+    }
 
-        Document::copy_until_marker(&mut reader, &mut writer, "__STYLES__")?;
-        self.stylesheet.write_styles(&mut writer).map_err(|e| e.to_string())?;
+    /// Save the document to a file.
+    ///
+    /// Files with the `.odt` extension are written as ODT packages, any other as Flat ODT.
+    pub fn save(&mut self, output: &PathBuf) -> Result<()> {
+        let mut file = File::create(output)?;
+        if output.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("odt")) {
+            self.generate_odt(&mut file)?;
+        } else {
+            self.generate_fodt(&mut file)?;
+        }
+        Ok(())
+    }
 
-        Document::copy_until_marker(&mut reader, &mut writer, "__AUTOMATIC_STYLES__")?;
-        self.stylesheet.write_automatic_styles(&mut writer).map_err(|e| e.to_string())?;
+    /// Export the document as an ODT package (ZIP), with images stored as `Pictures/*` entries.
+    pub fn generate_odt(&mut self, out: &mut dyn Write) -> Result<()> {
+        let mut flat = Vec::new();
+        self.generate_fodt(&mut flat)?;
+        package::write_odt(&flat, out)
+    }
 
-        Document::copy_until_marker(&mut reader, &mut writer, "__HEADER__")?;
-        self.write_header(&mut writer).map_err(|e| e.to_string())?;
+    /// Replace the `{{field}}` placeholders of the document (template, body, headers...) with
+    /// the values of a record (a `MergeData`, a `HashMap` or any serializable value).
+    pub fn set_merge_data<T: Serialize + ?Sized>(&mut self, record: &T) -> Result<()> {
+        self.merge_data = Some(MergeData::from_serialize(record)?);
+        Ok(())
+    }
 
-        //Document::copy_until_marker(&mut reader, &mut writer, "__FOOTER__")?;
-        //self.write_header(&mut writer).map_err(|e| e.to_string())?;
+    /// Generate one Flat ODT document per record, replacing the `{{field}}` placeholders
+    /// with the values of the record (see `set_merge_data`).
+    ///
+    /// The document is generated once, the placeholders being replaced for each record
+    /// when the iterator is advanced.
+    pub fn merge_records<I, T>(&mut self, records: I) -> Result<MergedDocuments<I::IntoIter>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+    {
+        let merge_data = self.merge_data.take();
+        let mut flat = Vec::new();
+        let generated = self.generate_fodt(&mut flat);
+        self.merge_data = merge_data;
+        generated?;
+        Ok(MergedDocuments { flat, records: records.into_iter() })
+    }
 
-        Document::copy_until_marker(&mut reader, &mut writer, "__BODY__")?;
-        if self.body.len() == 0 {
+    /// Export the document as Flat ODT (single XML)
+    pub fn generate_fodt(&mut self, out: &mut dyn Write) -> Result<()> {
+        if let Some(merge_data) = self.merge_data.take() {
+            let mut flat = Vec::new();
+            let generated = self.generate_fodt(&mut flat);
+            self.merge_data = Some(merge_data);
+            generated?;
+            return self.merge_data.as_ref().map_or(Ok(()), |data| data.substitute(&flat, out));
+        }
+        if self.body.is_empty() && self.slots.is_empty() {
             self.body.add(Paragraph::from_text_and_style("", "Standard"));
         }
-        self.body.write_flat_odt_xml(&mut writer).map_err(|e| e.to_string())?;
+        self.register_content_styles();
+        let mut writer = EmitterConfig::new()
+            .perform_indent(true)
+            .create_writer(out);
+        let mut numbering = self.note_numbering();
+        self.number_notes(&mut numbering, true)?;
+        self.number_notes(&mut numbering, false)?;
+        let mut template = TemplateCopy::new(self, true)?;
+        if template.copy(self, &mut writer)? {
+            self.body.write_flat_odt_xml(&mut writer)?;
+            template.copy(self, &mut writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Copy of the template, replacing the markers by the sections of the document.
+///
+/// The copy is interrupted at the position of the body, written by the caller.
+pub(crate) struct TemplateCopy {
+    reader: EventReader<Cursor<Vec<u8>>>,
+    /// Namespaces used by the generated elements, added to the ones of the template
+    namespace: Namespace,
+    /// Template elements replaced by the document
+    overridden: HashSet<String>,
+    /// Sections written at the end of their element, as the template has no marker for them
+    unmarked_sections: Vec<(&'static str, &'static str)>,
+    /// Names of the open elements
+    elements: Vec<String>,
+    skipped_depth: usize,
+    /// Event read when reaching the body, written when the copy resumes
+    pending: Option<REvent>,
+    /// Whether the statistics of the body are written with the metadata
+    statistics: bool,
+}
+
+impl TemplateCopy {
+    pub(crate) fn new(doc: &Document, statistics: bool) -> Result<Self> {
+        let markers = Document::template_markers(&doc.template)?;
+        let reader = EventReader::new_with_config(
+            Cursor::new(doc.template.as_bytes().to_vec()),
+            ParserConfig::new()
+                .ignore_comments(false)
+        );
+        Ok(TemplateCopy {
+            reader,
+            namespace: root_namespace(BLANK_FODT)?,
+            overridden: doc.overridden_template_elements(),
+            unmarked_sections: SECTION_MARKERS.iter()
+                .filter(|(marker, _)| !markers.iter().any(|m| m == marker))
+                .copied()
+                .collect(),
+            elements: Vec::new(),
+            skipped_depth: 0,
+            pending: None,
+            statistics,
+        })
+    }
 
-        // resto do documento
+    /// Copy the template until the position of the body or the end of the document.
+    ///
+    /// Returns true if the copy stopped at the body.
+    pub(crate) fn copy(&mut self, doc: &Document, writer: &mut EventWriter<&mut dyn Write>) -> Result<bool> {
+        if let Some(ev) = self.pending.take() {
+            TemplateCopy::write_event(writer, ev)?;
+        }
         loop {
-            let read_evt = reader.next().map_err(|e| e.to_string())?;
-            if matches!(read_evt, REvent::EndDocument) {
-                break;
+            let mut ev = self.reader.next()?;
+            if self.skipped_depth > 0 {
+                match ev {
+                    REvent::StartElement { .. } => self.skipped_depth += 1,
+                    REvent::EndElement { .. } => self.skipped_depth -= 1,
+                    _ => {}
+                }
+                //The end of a skipped `office:meta` is kept
+                if self.skipped_depth > 0 || self.elements.last().is_none_or(|e| e != "office:meta") {
+                    continue;
+                }
+            }
+            match ev {
+                REvent::EndDocument => return Ok(false),
+                REvent::Comment(text) => {
+                    //Other comments are dropped
+                    match text.trim() {
+                        "__BODY__" => return Ok(true),
+                        marker => self.write_section(doc, marker, writer)?,
+                    }
+                    continue;
+                }
+                REvent::StartElement { ref name, ref attributes, namespace: ref mut element_namespace } => {
+                    if self.overridden.contains(&Document::template_style_key(name, attributes)) {
+                        let section = doc.template_sections.iter()
+                            .find(|s| name.namespace.as_deref().is_some_and(|uri| s.is(uri, &name.local_name)));
+                        if let Some(section) = section {
+                            section.write_flat_odt_xml(writer)?;
+                        }
+                        self.skipped_depth = 1;
+                        continue;
+                    }
+                    if self.elements.is_empty() {
+                        for (prefix, uri) in &self.namespace {
+                            element_namespace.put(prefix, uri);
+                        }
+                    }
+                    self.elements.push(name.borrow().repr_display().to_string());
+                }
+                REvent::EndElement { .. } => {
+                    let element = self.elements.pop().unwrap_or_default();
+                    let markers: Vec<&str> = self.unmarked_sections.iter()
+                        .filter(|(_, e)| *e == element)
+                        .map(|(marker, _)| *marker)
+                        .collect();
+                    for marker in markers {
+                        if marker == "__BODY__" {
+                            self.pending = Some(ev);
+                            return Ok(true);
+                        }
+                        self.write_section(doc, marker, writer)?;
+                    }
+                }
+                _ => {}
+            }
+            let is_meta_start = matches!(ev, REvent::StartElement { .. })
+                && self.elements.last().is_some_and(|e| e == "office:meta");
+            TemplateCopy::write_event(writer, ev)?;
+            //Without marker, the metadata of the template is replaced
+            if is_meta_start && self.unmarked_sections.iter().any(|(marker, _)| *marker == "__META__") {
+                self.skipped_depth = 1;
             }
-            let ev = read_evt.as_writer_event().unwrap();
-            writer.write(ev).map_err(|e| e.to_string())?;
         }
+    }
+
+    fn write_event(writer: &mut EventWriter<&mut dyn Write>, ev: REvent) -> Result<()> {
+        let ev = ev.as_writer_event()
+            .ok_or_else(|| Error::Xml("Failed to build a writer XmlEvent!".to_string()))?;
+        writer.write(ev)?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Write the section of the document replacing a template marker (unknown markers are ignored).
+    fn write_section(&self, doc: &Document, marker: &str, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        match marker {
+            "__META__" => {
+                doc.metadata.write_flat_odt_xml(writer)?;
+                if self.statistics {
+                    doc.statistics().write_flat_odt_xml(writer)?;
+                }
+            }
+            "__STYLES__" => doc.stylesheet.write_styles(writer)?,
+            "__NOTES_CONFIGURATION__" => {
+                doc.footnotes_configuration.write_flat_odt_xml(writer)?;
+                doc.endnotes_configuration.write_flat_odt_xml(writer)?;
+            }
+            "__AUTOMATIC_STYLES__" => doc.stylesheet.write_automatic_styles(writer)?,
+            "__MASTER_PAGES__" => doc.write_master_pages(writer)?,
+            _ => {
+                let slot = marker.strip_prefix("__SLOT:").and_then(|m| m.strip_suffix("__"))
+                    .and_then(|name| doc.slots.iter().find(|(slot_name, _)| slot_name == name));
+                if let Some((_, content)) = slot {
+                    content.write_flat_odt_xml(writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the documents generated by `Document::merge_records`
+pub struct MergedDocuments<I> {
+    flat: Vec<u8>,
+    records: I,
+}
+
+impl<I, T> Iterator for MergedDocuments<I>
+where
+    I: Iterator<Item = T>,
+    T: Serialize,
+{
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.records.next()?;
+        let mut out = Vec::new();
+        Some(MergeData::from_serialize(&record)
+            .and_then(|data| data.substitute(&self.flat, &mut out))
+            .map(|_| out))
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document_with_master_pages(names: &[&str]) -> Document {
+        let mut doc = Document::new();
+        for name in names {
+            doc.add_master_page(MasterPage::new(*name, "pm1")).unwrap();
+        }
+        doc
+    }
+
+    #[test]
+    fn master_page_styles_are_keyed_on_master_and_parent() {
+        let mut doc = document_with_master_pages(&["A_B", "A"]);
+        let first = doc.master_page_style("A_B", "C").unwrap();
+        let second = doc.master_page_style("A", "B_C").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(doc.master_page_style("A_B", "C").unwrap(), first);
+        assert_eq!(doc.master_page_style("A", "B_C").unwrap(), second);
+
+        let text_body = doc.master_page_style("Standard", "Text Body").unwrap();
+        let text_underscore_body = doc.master_page_style("Standard", "Text_Body").unwrap();
+        assert_ne!(text_body, text_underscore_body);
+        let style = doc.stylesheet.styles().find(|s| s.name() == text_body).unwrap();
+        assert_eq!(style.parent_style_name.as_deref(), Some("Text Body"));
+        assert_eq!(style.master_page_name.as_deref(), Some("Standard"));
+    }
+
+    #[test]
+    fn template_outline_style_is_written_once() {
+        let template = include_str!("../tests/data/libreoffice.fodt");
+        let mut doc = Document::with_template_str(template).unwrap();
+        let mut out = Vec::new();
+        doc.generate_fodt(&mut out).unwrap();
+        let flat = String::from_utf8(out).unwrap();
+        assert_eq!(flat.matches("<text:outline-style").count(), 1);
+
+        //odtgen's own output used as a template
+        let mut doc = Document::with_template_str(flat).unwrap();
+        let mut out = Vec::new();
+        doc.generate_fodt(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().matches("<text:outline-style").count(), 1);
+    }
+
+    #[test]
+    fn undefined_and_standard_master_pages_are_rejected() {
+        let mut doc = Document::new();
+        assert!(doc.master_page_style("Missing", "Standard").is_err());
+        assert!(doc.add_master_page(MasterPage::new("Standard", "pm1")).is_err());
+        assert!(doc.master_pages.is_empty());
+    }
+}
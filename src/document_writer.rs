@@ -0,0 +1,199 @@
+//! Streaming output of large documents
+//!
+//! The parts of the template preceding the body (metadata, styles, master pages) are written
+//! as soon as the writer is created. Body elements and table rows are then written one at a
+//! time, without being stored, so the memory usage does not grow with the document size.
+
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{EmitterConfig, XmlEvent as XmlWriterEvent};
+use crate::document::{Document, TemplateCopy};
+use crate::error::{Error, Result};
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::note::NoteNumbering;
+use crate::table::{Table, TableLayout, TableRow};
+
+/// Writer of a Flat ODT document which body is streamed.
+///
+/// The document statistics are not written, as the body is not known when writing the
+/// metadata. Merge data (see `Document::set_merge_data`) is ignored. The automatic styles
+/// of the streamed elements (e.g. column widths) must be added with
+/// `Document::register_automatic_styles` before creating the writer.
+///
+/// The document is only complete once `finish` is called: dropping the writer before leaves
+/// the output truncated at the last written element.
+pub struct DocumentWriter<'a> {
+    document: Document,
+    template: TemplateCopy,
+    writer: EventWriter<&'a mut dyn Write>,
+    /// Layout of the table started by `start_table`, if open
+    table: Option<TableLayout>,
+    /// Numbering of the notes, continued by the streamed elements
+    numbering: NoteNumbering,
+}
+
+impl<'a> DocumentWriter<'a> {
+    /// Write the document until its body, including the elements already added to the body.
+    #[must_use = "the document is truncated unless `finish` is called"]
+    pub fn new(mut document: Document, out: &'a mut dyn Write) -> Result<Self> {
+        document.register_content_styles();
+        let mut writer = EmitterConfig::new()
+            .perform_indent(true)
+            .create_writer(out);
+        let mut numbering = document.note_numbering();
+        document.number_notes(&mut numbering, true)?;
+        let mut template = TemplateCopy::new(&document, false)?;
+        if !template.copy(&document, &mut writer)? {
+            return Err(Error::Template("The template has no body!".to_string()));
+        }
+        document.body.write_flat_odt_xml(&mut writer)?;
+        Ok(DocumentWriter {
+            document,
+            template,
+            writer,
+            table: None,
+            numbering,
+        })
+    }
+
+    /// Write an element (paragraph, heading, list, table...) at the end of the body.
+    ///
+    /// A table started by `start_table` is finished first. The notes of the element are
+    /// numbered after the ones already written.
+    pub fn add<T: FlatOdtXmlWrite + ?Sized>(&mut self, element: &mut T) -> Result<()> {
+        self.end_table()?;
+        element.number_notes(&mut self.numbering);
+        element.write_flat_odt_xml(&mut self.writer)
+    }
+
+    /// Start a table which rows are added with `add_row`.
+    ///
+    /// The columns, header rows and rows already in `table` are written immediately.
+    pub fn start_table(&mut self, table: &mut Table) -> Result<()> {
+        self.end_table()?;
+        table.number_notes(&mut self.numbering);
+        let mut layout = table.write_start(&mut self.writer)?;
+        for row in &table.rows {
+            layout.write_row(row, &mut self.writer)?;
+        }
+        self.table = Some(layout);
+        Ok(())
+    }
+
+    /// Write a row of the table started by `start_table`.
+    ///
+    /// As the end of the table is unknown, the last row style of a table preset is not used.
+    pub fn add_row(&mut self, row: &mut TableRow) -> Result<()> {
+        match self.table {
+            Some(ref mut layout) => {
+                row.number_notes(&mut self.numbering);
+                layout.write_row(row, &mut self.writer)
+            }
+            None => Err(Error::Table("Table row added without a started table!".to_string())),
+        }
+    }
+
+    /// Finish the table started by `start_table` (nothing happens if no table is open).
+    pub fn end_table(&mut self) -> Result<()> {
+        if let Some(layout) = self.table.take() {
+            layout.check_spans_ended()?;
+            self.writer.write(XmlWriterEvent::end_element())?;
+        }
+        Ok(())
+    }
+
+    /// Finish the body and write the rest of the template.
+    pub fn finish(mut self) -> Result<()> {
+        self.end_table()?;
+        self.document.number_notes(&mut self.numbering, false)?;
+        self.template.copy(&self.document, &mut self.writer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::Note;
+    use crate::paragraph::Paragraph;
+    use crate::raw_xml::RawXml;
+    use crate::table::{TableCell, TableColumn};
+
+    fn row(text: &str) -> TableRow {
+        let mut cell = TableCell::new();
+        cell.content.add(Paragraph::from_text(text));
+        let mut row = TableRow::new();
+        row.add_cell(cell);
+        row
+    }
+
+    fn paragraph_with_footnote(text: &str) -> Paragraph {
+        let mut paragraph = Paragraph::from_text(text);
+        paragraph.content.add(Note::footnote("Note"));
+        paragraph
+    }
+
+    #[test]
+    fn streamed_document_is_well_formed() {
+        let mut document = Document::new();
+        document.body.add(Paragraph::from_text("Before"));
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = DocumentWriter::new(document, &mut out).unwrap();
+        writer.add(&mut Paragraph::from_text("Streamed")).unwrap();
+        let mut table = Table::new("Streamed");
+        table.add_column(TableColumn::new());
+        writer.start_table(&mut table).unwrap();
+        for i in 0..3 {
+            writer.add_row(&mut row(&format!("Row {}", i))).unwrap();
+        }
+        writer.add(&mut Paragraph::from_text("After")).unwrap();
+        writer.finish().unwrap();
+
+        let flat = String::from_utf8(out).unwrap();
+        assert!(RawXml::read_document(flat.as_bytes()).is_ok());
+        assert!(flat.ends_with("</office:document>"));
+        assert_eq!(flat.matches("<table:table-row>").count(), 3);
+        let positions: Vec<usize> = [">Before<", ">Streamed<", ">Row 0<", ">Row 2<", "</table:table>", ">After<"].iter()
+            .map(|text| flat.find(text).unwrap())
+            .collect();
+        assert!(positions.is_sorted(), "{:?}", positions);
+    }
+
+    #[test]
+    fn streamed_notes_continue_the_numbering() {
+        let mut document = Document::new();
+        document.body.add(paragraph_with_footnote("Before"));
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = DocumentWriter::new(document, &mut out).unwrap();
+        writer.add(&mut paragraph_with_footnote("Streamed")).unwrap();
+        let mut table = Table::new("Streamed");
+        table.add_column(TableColumn::new());
+        writer.start_table(&mut table).unwrap();
+        let mut row = TableRow::new();
+        let mut cell = TableCell::new();
+        cell.content.add(paragraph_with_footnote("In a row"));
+        row.add_cell(cell);
+        writer.add_row(&mut row).unwrap();
+        writer.finish().unwrap();
+
+        let flat = String::from_utf8(out).unwrap();
+        let citations: Vec<&str> = flat.split("<text:note-citation").skip(1)
+            .map(|s| &s[s.find('>').unwrap() + 1..s.find('<').unwrap()])
+            .collect();
+        assert_eq!(citations, ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn rows_need_a_started_table() {
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = DocumentWriter::new(Document::new(), &mut out).unwrap();
+        assert!(matches!(writer.add_row(&mut row("Orphan")), Err(Error::Table(_))));
+        let mut table = Table::new("Ended");
+        table.add_column(TableColumn::new());
+        writer.start_table(&mut table).unwrap();
+        writer.end_table().unwrap();
+        assert!(matches!(writer.add_row(&mut row("Orphan")), Err(Error::Table(_))));
+        writer.finish().unwrap();
+        assert!(!String::from_utf8(out).unwrap().contains("Orphan"));
+    }
+}
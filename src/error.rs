@@ -0,0 +1,105 @@
+//! Error type shared by the whole crate
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Errors produced while building, parsing or exporting documents
+#[derive(Debug)]
+pub enum Error {
+    /// Failure of the underlying reader/writer
+    Io(std::io::Error),
+    /// Malformed XML (when reading) or invalid XML event sequence (when writing)
+    Xml(String),
+    /// Invalid template (e.g. a missing marker)
+    Template(String),
+    /// Unsupported document structure (when parsing Flat ODT files)
+    Parse(String),
+    /// Invalid table structure (e.g. rows not matching the columns)
+    Table(String),
+    /// Invalid heading (e.g. an outline level outside 1 to 10)
+    Heading(String),
+    /// Invalid stylesheet definition
+    Stylesheet {
+        /// Name of the offending style (empty when it could not be determined)
+        style: String,
+        /// Offending key
+        key: String,
+        message: String,
+    },
+    /// An image file could not be read
+    MissingImage {
+        path: PathBuf,
+    },
+}
+
+/// Result type of the crate
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Helper for building stylesheet errors.
+    pub(crate) fn stylesheet<S, K, M>(style: S, key: K, message: M) -> Self
+    where
+        S: Into<String>,
+        K: Into<String>,
+        M: Into<String>,
+    {
+        Error::Stylesheet {
+            style: style.into(),
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Xml(message) => write!(f, "XML error: {}", message),
+            Error::Template(message) => write!(f, "Template error: {}", message),
+            Error::Parse(message) => write!(f, "Parse error: {}", message),
+            Error::Table(message) => write!(f, "Table error: {}", message),
+            Error::Heading(message) => write!(f, "Heading error: {}", message),
+            Error::Stylesheet { style, key, message } => {
+                write!(f, "Stylesheet error (style '{}', key '{}'): {}", style, key, message)
+            }
+            Error::MissingImage { path } => write!(f, "Failed to read image: {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<xml::writer::Error> for Error {
+    fn from(e: xml::writer::Error) -> Self {
+        match e {
+            xml::writer::Error::Io(e) => Error::Io(e),
+            other => Error::Xml(other.to_string()),
+        }
+    }
+}
+
+impl From<xml::reader::Error> for Error {
+    fn from(e: xml::reader::Error) -> Self {
+        Error::Xml(e.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(e: zip::result::ZipError) -> Self {
+        Error::Io(e.into())
+    }
+}
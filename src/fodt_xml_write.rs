@@ -1,12 +1,31 @@
 use std::io::Write;
 use xml::writer::events::StartElementBuilder;
+use crate::error::Result;
+use crate::note::NoteNumbering;
+use crate::statistics::StatisticsWalker;
+use crate::stylesheet::Stylesheet;
 
 pub trait FlatOdtXmlWrite {
     /// Export to XML
     fn write_flat_odt_xml(
         &self,
         writer: &mut xml::writer::EventWriter<&mut dyn Write>,
-    ) -> xml::writer::Result<()>;
+    ) -> Result<()>;
+
+    /// Update document statistics with this element and its children.
+    fn update_statistics(&self, _statistics: &mut StatisticsWalker) {}
+
+    /// Add the automatic styles needed by this element and its children (e.g. column widths).
+    fn register_automatic_styles(&self, _stylesheet: &mut Stylesheet) {}
+
+    /// Number the notes of this element and its children, before writing them.
+    fn number_notes(&mut self, _numbering: &mut NoteNumbering) {}
+
+    /// Whether the element is a block (paragraph, heading, list, table...), allowed directly
+    /// in a table cell. Inline elements (text, spans, images...) must be put in a paragraph.
+    fn is_block(&self) -> bool {
+        false
+    }
 
     /// Optional XML attributes with direct mapping to struct optional attributes.
     fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
@@ -0,0 +1,155 @@
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::container::Container;
+use crate::error::{Error, Result};
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::note::NoteNumbering;
+use crate::statistics::StatisticsWalker;
+use crate::stylesheet::Stylesheet;
+use crate::text::Text;
+
+/// A heading (`text:h`), listed in the navigator and tables of contents.
+///
+/// Numbering of headings is controlled by the outline style of the stylesheet.
+pub struct Heading {
+    pub style_name: Option<String>,
+    /// Outline level, from 1 to 10
+    outline_level: u8,
+    /// Restart the outline numbering at this heading
+    pub restart_numbering: bool,
+    /// Value of the outline number when `restart_numbering` is set
+    pub start_value: Option<u32>,
+    pub content: Container,
+}
+
+/// Highest outline level of ODF headings
+pub const MAX_OUTLINE_LEVEL: u8 = 10;
+
+impl Heading {
+    /// Create an empty heading, failing if the outline level is not between 1 and 10.
+    pub fn new(outline_level: u8) -> Result<Self> {
+        Heading::check_outline_level(outline_level)?;
+        Ok(Self {
+            style_name: None,
+            outline_level,
+            restart_numbering: false,
+            start_value: None,
+            content: Container::new(),
+        })
+    }
+
+    /// Helper to create a heading using the default `HeadingN` style of the level.
+    pub fn from_text<T: Into<String>>(text: T, outline_level: u8) -> Result<Self> {
+        Heading::from_text_and_style(text, format!("Heading{}", outline_level), outline_level)
+    }
+
+    /// Helper to create a simple heading with some text and a given style.
+    pub fn from_text_and_style<T: Into<String>, U: Into<String>>(text: T, style_name: U, outline_level: u8) -> Result<Self> {
+        let mut heading = Heading::new(outline_level)?;
+        heading.content.add(Text {text: text.into()});
+        heading.style_name = Some(style_name.into());
+        Ok(heading)
+    }
+
+    pub fn outline_level(&self) -> u8 {
+        self.outline_level
+    }
+
+    /// Change the outline level, failing if it is not between 1 and 10.
+    pub fn set_outline_level(&mut self, outline_level: u8) -> Result<()> {
+        Heading::check_outline_level(outline_level)?;
+        self.outline_level = outline_level;
+        Ok(())
+    }
+
+    fn check_outline_level(outline_level: u8) -> Result<()> {
+        if !(1..=MAX_OUTLINE_LEVEL).contains(&outline_level) {
+            return Err(Error::Heading(format!(
+                "Invalid outline level {} (expected 1 to {})!", outline_level, MAX_OUTLINE_LEVEL
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl FlatOdtXmlWrite for Heading {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let level = self.outline_level.to_string();
+        let start_value: String;
+        let mut start = XmlWriterEvent::start_element("text:h");
+        start = self.add_optional_string_attributes(start);
+        start = start.attr("text:outline-level", level.as_str());
+        if self.restart_numbering {
+            start = start.attr("text:restart-numbering", "true");
+            if let Some(ref value) = self.start_value {
+                start_value = value.to_string();
+                start = start.attr("text:start-value", start_value.as_str());
+            }
+        }
+        writer.write(start)?;
+        self.content.write_flat_odt_xml(writer)?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        statistics.add_paragraph();
+        self.content.update_statistics(statistics);
+    }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        self.content.number_notes(numbering);
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
+        self.content.register_automatic_styles(stylesheet);
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
+        vec![
+            ("text:style-name", self.style_name.as_deref()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xml::writer::EmitterConfig;
+    use crate::stylesheet::Stylesheet;
+    use super::*;
+
+    fn write(heading: &Heading) -> Result<String> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut out as &mut dyn Write);
+        heading.write_flat_odt_xml(&mut writer)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn outline_levels_outside_1_to_10_are_rejected() {
+        assert!(matches!(Heading::new(0), Err(Error::Heading(_))));
+        assert!(matches!(Heading::from_text("Eleven", MAX_OUTLINE_LEVEL + 1), Err(Error::Heading(_))));
+        let one = Heading::from_text("One", 1).unwrap();
+        assert!(write(&one).unwrap().contains("text:outline-level=\"1\""));
+        let mut heading = Heading::from_text("Ten", MAX_OUTLINE_LEVEL).unwrap();
+        assert!(write(&heading).unwrap().contains("text:outline-level=\"10\""));
+        assert!(matches!(heading.set_outline_level(11), Err(Error::Heading(_))));
+        assert!(matches!(heading.set_outline_level(0), Err(Error::Heading(_))));
+        assert_eq!(heading.outline_level(), MAX_OUTLINE_LEVEL);
+        heading.set_outline_level(1).unwrap();
+        assert_eq!(heading.outline_level(), 1);
+    }
+
+    #[test]
+    fn default_stylesheet_has_a_style_per_level() {
+        let stylesheet = Stylesheet::default();
+        for level in 1..=MAX_OUTLINE_LEVEL {
+            assert!(stylesheet.contains(&format!("Heading{}", level)), "Heading{}", level);
+        }
+    }
+}
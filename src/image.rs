@@ -1,7 +1,9 @@
 use std::io::Write;
 use std::path::PathBuf;
 use xml::EventWriter;
+use crate::error::{Error, Result};
 use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::statistics::StatisticsWalker;
 use xml::writer::{XmlEvent as XmlWriterEvent};
 use base64::prelude::*;
 use strum_macros::{AsRefStr, EnumString};
@@ -22,6 +24,7 @@ pub enum ImageAnchor {
 }
 
 impl Image {
+    #[allow(clippy::ptr_arg)]
     pub fn new(path: &PathBuf, width_cm: f32, height_cm: f32, anchor: ImageAnchor) -> Self {
         Self {
             path: path.clone(),
@@ -41,10 +44,10 @@ impl Image {
 }
 
 impl FlatOdtXmlWrite for Image {
-    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
 
         let image_data = std::fs::read(&self.path)
-            .expect("Falha ao ler imagem");
+            .map_err(|_| Error::MissingImage { path: self.path.clone() })?;
 
         let base64_data = BASE64_STANDARD.encode(image_data);
 
@@ -65,6 +68,11 @@ impl FlatOdtXmlWrite for Image {
         writer.write(XmlWriterEvent::end_element())?; // binary-data
 
         writer.write(XmlWriterEvent::end_element())?; // draw:image
-        writer.write(XmlWriterEvent::end_element()) // draw:frame
+        writer.write(XmlWriterEvent::end_element())?; // draw:frame
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        statistics.add_image();
     }
 }
\ No newline at end of file
@@ -1,25 +1,64 @@
 #![doc = include_str!("../README.md")]
 
 pub mod container;
+pub mod note;
+pub mod outline_style;
 pub mod paragraph;
+pub mod raw_xml;
 pub mod document;
+pub mod document_writer;
+pub mod error;
+pub mod fodt_parser;
 pub mod fodt_xml_write;
+pub mod statistics;
 pub mod style;
 pub mod table;
+pub mod table_import;
+pub mod table_preset;
 pub mod stylesheet;
 pub mod stylesheet_parser;
+pub mod heading;
 pub mod image;
+pub mod link;
+pub mod list;
+pub mod list_style;
+pub mod master_page;
+pub mod merge;
+pub mod metadata;
 pub mod text;
 pub mod text_span;
 pub mod tab;
+mod package;
+pub mod page_layout;
+
+#[cfg(feature = "derive")]
+pub use odtgen_derive::OdtTableRow;
 
 pub mod prelude {
     pub use crate::document::Document;
+    pub use crate::document_writer::DocumentWriter;
+    pub use crate::error::Error;
+    pub use crate::heading::Heading;
+    pub use crate::note::{Note, NoteClass, NoteRestart, NotesConfiguration};
+    pub use crate::outline_style::OutlineStyle;
     pub use crate::paragraph::Paragraph;
-    pub use crate::table::{Table, TableColumn, TableRow, TableCell};
+    pub use crate::table::{CellValue, Table, TableAlignment, TableColumn, TableRow, TableCell};
+    pub use crate::table_import::{OdtTableRow, TableOptions, ToTableCell};
+    pub use crate::table_preset::TablePreset;
+    #[cfg(feature = "derive")]
+    pub use odtgen_derive::OdtTableRow;
     pub use crate::text::Text;
+    pub use crate::text_span::TextSpan;
     pub use crate::image::{Image, ImageAnchor};
+    pub use crate::link::Link;
+    pub use crate::list::{List, ListContinuation, ListItem};
+    pub use crate::list_style::{ListLabel, ListLevel, ListStyle};
     pub use crate::style::{Style, StyleFamily, StyleItem, StylePropertyGroup};
+    pub use crate::master_page::MasterPage;
+    pub use crate::merge::MergeData;
+    pub use crate::metadata::{MetaValue, Metadata};
+    pub use crate::page_layout::{PageLayout, PaperSize, PrintOrientation};
+    pub use crate::statistics::DocumentStatistics;
     pub use crate::stylesheet::Stylesheet;
     pub use crate::stylesheet_parser::StylesheetParser;
 }
\ No newline at end of file
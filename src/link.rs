@@ -0,0 +1,124 @@
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::container::Container;
+use crate::error::Result;
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::note::NoteNumbering;
+use crate::statistics::StatisticsWalker;
+use crate::stylesheet::Stylesheet;
+use crate::text::Text;
+
+/// A hyperlink (`text:a`) with inline content (`Text`, `TextSpan`...).
+///
+/// New links use the `InternetLink` and `VisitedInternetLink` character styles of the
+/// default stylesheet.
+pub struct Link {
+    pub href: String,
+    /// Frame where the link is opened (e.g. `_blank`)
+    pub target_frame_name: Option<String>,
+    pub title: Option<String>,
+    /// Character style of unvisited links
+    pub style_name: Option<String>,
+    /// Character style of visited links
+    pub visited_style_name: Option<String>,
+    pub content: Container,
+}
+
+impl Link {
+    pub fn new<T: Into<String>>(href: T) -> Self {
+        Self {
+            href: href.into(),
+            target_frame_name: None,
+            title: None,
+            style_name: Some("InternetLink".to_string()),
+            visited_style_name: Some("VisitedInternetLink".to_string()),
+            content: Container::new(),
+        }
+    }
+
+    /// Helper to create a link with some text.
+    pub fn from_text<T: Into<String>, U: Into<String>>(text: T, href: U) -> Self {
+        let mut link = Link::new(href);
+        link.content.add(Text {text: text.into()});
+        link
+    }
+}
+
+impl FlatOdtXmlWrite for Link {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let mut start = XmlWriterEvent::start_element("text:a")
+            .attr("xlink:type", "simple")
+            .attr("xlink:href", &self.href);
+        start = self.add_optional_string_attributes(start);
+        if let Some(ref target) = self.target_frame_name {
+            let show = if target == "_blank" { "new" } else { "replace" };
+            start = start.attr("xlink:show", show);
+        }
+        writer.write(start)?;
+        self.content.write_flat_odt_xml(writer)?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        self.content.update_statistics(statistics);
+    }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        self.content.number_notes(numbering);
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
+        self.content.register_automatic_styles(stylesheet);
+    }
+
+    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
+        vec![
+            ("office:target-frame-name", self.target_frame_name.as_deref()),
+            ("office:title", self.title.as_deref()),
+            ("text:style-name", self.style_name.as_deref()),
+            ("text:visited-style-name", self.visited_style_name.as_deref()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xml::writer::EmitterConfig;
+    use super::*;
+
+    fn write<T: FlatOdtXmlWrite>(element: &T) -> Result<String> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut out as &mut dyn Write);
+        element.write_flat_odt_xml(&mut writer)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn link_to_frame(target: Option<&str>) -> String {
+        let mut link = Link::from_text("Site", "https://example.org");
+        link.target_frame_name = target.map(str::to_string);
+        write(&link).unwrap()
+    }
+
+    #[test]
+    fn links_without_target_frame_have_no_show_attribute() {
+        let xml = link_to_frame(None);
+        assert!(xml.starts_with("<text:a xlink:type=\"simple\" xlink:href=\"https://example.org\""), "{}", xml);
+        assert!(xml.contains("text:style-name=\"InternetLink\" text:visited-style-name=\"VisitedInternetLink\""));
+        assert!(!xml.contains("xlink:show") && !xml.contains("office:target-frame-name"));
+        assert!(xml.ends_with(">Site</text:a>"));
+    }
+
+    #[test]
+    fn target_frames_are_mapped_to_show_values() {
+        let xml = link_to_frame(Some("_blank"));
+        assert!(xml.contains("office:target-frame-name=\"_blank\""), "{}", xml);
+        assert!(xml.contains("xlink:show=\"new\""), "{}", xml);
+        for target in ["_self", "_top", "content"] {
+            let xml = link_to_frame(Some(target));
+            assert!(xml.contains(&format!("office:target-frame-name=\"{}\"", target)), "{}", xml);
+            assert!(xml.contains("xlink:show=\"replace\""), "{}", xml);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::container::Container;
+use crate::error::Result;
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::note::NoteNumbering;
+use crate::paragraph::Paragraph;
+use crate::statistics::StatisticsWalker;
+use crate::stylesheet::Stylesheet;
+
+/// An ordered or unordered list (`text:list`).
+///
+/// Whether the list is numbered or bulleted depends on the referenced list style.
+/// Lists are nested by adding a `List` to the content of a `ListItem`.
+pub struct List {
+    pub style_name: Option<String>,
+    /// Identifier (`xml:id`) used by other lists to continue this one
+    pub id: Option<String>,
+    /// Numbering continued from another list, if any
+    pub continuation: ListContinuation,
+    pub items: Vec<ListItem>,
+}
+
+/// Numbering continued from another list (ODF allows only one of the two attributes)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ListContinuation {
+    /// The numbering restarts
+    #[default]
+    None,
+    /// Continue the numbering of the preceding list with the same style (`text:continue-numbering`)
+    Preceding,
+    /// Continue the numbering of the list with the given `id` (`text:continue-list`)
+    List(String),
+}
+
+impl List {
+    pub fn new() -> Self {
+        Self {
+            style_name: None,
+            id: None,
+            continuation: ListContinuation::None,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn new_with_style<T: Into<String>>(style_name: T) -> Self {
+        let mut list = List::new();
+        list.style_name = Some(style_name.into());
+        list
+    }
+
+    /// Helper to create a flat list with one paragraph per item.
+    pub fn from_items<I, T>(items: I, style_name: Option<&str>) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let mut list = List::new();
+        list.style_name = style_name.map(str::to_string);
+        for item in items {
+            list.add_item(ListItem::from_text(item));
+        }
+        list
+    }
+
+    pub fn add_item(&mut self, item: ListItem) {
+        self.items.push(item);
+    }
+}
+
+impl Default for List {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatOdtXmlWrite for List {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let mut start = XmlWriterEvent::start_element("text:list");
+        start = self.add_optional_string_attributes(start);
+        match self.continuation {
+            ListContinuation::None => {}
+            ListContinuation::Preceding => start = start.attr("text:continue-numbering", "true"),
+            ListContinuation::List(ref id) => start = start.attr("text:continue-list", id),
+        }
+        writer.write(start)?;
+        for item in &self.items {
+            item.write_flat_odt_xml(writer)?;
+        }
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        for item in &self.items {
+            item.update_statistics(statistics);
+        }
+    }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        for item in &mut self.items {
+            item.number_notes(numbering);
+        }
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
+        for item in &self.items {
+            item.register_automatic_styles(stylesheet);
+        }
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+
+    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
+        vec![
+            ("xml:id", self.id.as_deref()),
+            ("text:style-name", self.style_name.as_deref()),
+        ]
+    }
+}
+
+// ===============================================================================================
+// Items
+// ===============================================================================================
+
+/// An item of a list (`text:list-item`), containing paragraphs and/or nested lists.
+pub struct ListItem {
+    /// Whether the item is a list header (`text:list-header`), without label nor number
+    pub header: bool,
+    /// Restart the numbering at this value
+    pub start_value: Option<u32>,
+    /// List style overriding the one of the list for this item
+    pub style_override: Option<String>,
+    pub content: Container,
+}
+
+impl ListItem {
+    pub fn new() -> Self {
+        Self {
+            header: false,
+            start_value: None,
+            style_override: None,
+            content: Container::new(),
+        }
+    }
+
+    /// Helper to create an item with a single paragraph.
+    pub fn from_text<T: Into<String>>(text: T) -> Self {
+        let mut item = ListItem::new();
+        item.content.add(Paragraph::from_text(text));
+        item
+    }
+
+    /// Helper to create an item with a single paragraph with a given style.
+    pub fn from_text_and_style<T: Into<String>, U: Into<String>>(text: T, style_name: U) -> Self {
+        let mut item = ListItem::new();
+        item.content.add(Paragraph::from_text_and_style(text, style_name));
+        item
+    }
+}
+
+impl Default for ListItem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatOdtXmlWrite for ListItem {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let attr: String;
+        let start = if self.header {
+            //Headers have no numbering nor style attributes
+            XmlWriterEvent::start_element("text:list-header")
+        } else {
+            let mut start = XmlWriterEvent::start_element("text:list-item");
+            start = self.add_optional_string_attributes(start);
+            if let Some(ref start_value) = self.start_value {
+                attr = start_value.to_string();
+                start = start.attr("text:start-value", attr.as_str());
+            }
+            start
+        };
+        writer.write(start)?;
+        self.content.write_flat_odt_xml(writer)?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        self.content.update_statistics(statistics);
+    }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        self.content.number_notes(numbering);
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
+        self.content.register_automatic_styles(stylesheet);
+    }
+
+    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
+        vec![
+            ("text:style-override", self.style_override.as_deref()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xml::writer::EmitterConfig;
+    use super::*;
+
+    fn write<T: FlatOdtXmlWrite>(element: &T) -> Result<String> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut out as &mut dyn Write);
+        element.write_flat_odt_xml(&mut writer)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn continuation_is_written_as_a_single_attribute() {
+        let mut list = List::from_items(["A"], Some("L1"));
+        assert!(!write(&list).unwrap().contains("text:continue"));
+        list.continuation = ListContinuation::Preceding;
+        let xml = write(&list).unwrap();
+        assert!(xml.contains("text:continue-numbering=\"true\""), "{}", xml);
+        assert!(!xml.contains("text:continue-list"));
+        list.continuation = ListContinuation::List("list1".to_string());
+        let xml = write(&list).unwrap();
+        assert!(xml.contains("text:continue-list=\"list1\""), "{}", xml);
+        assert!(!xml.contains("text:continue-numbering"));
+    }
+
+    #[test]
+    fn headers_are_written_without_numbering_attributes() {
+        let mut header = ListItem::from_text("Header");
+        header.header = true;
+        header.start_value = Some(3);
+        header.style_override = Some("L2".to_string());
+        let mut list = List::new_with_style("L1");
+        list.add_item(header);
+        list.add_item(ListItem::from_text("Item"));
+        let xml = write(&list).unwrap();
+        assert!(xml.contains("<text:list-header><text:p>Header</text:p></text:list-header>"), "{}", xml);
+        assert!(xml.contains("<text:list-item><text:p>Item</text:p></text:list-item>"), "{}", xml);
+        assert!(!xml.contains("text:start-value") && !xml.contains("text:style-override"));
+    }
+}
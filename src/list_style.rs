@@ -0,0 +1,251 @@
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::error::Result;
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+
+/// Number of levels supported by ODF lists
+pub const LIST_LEVELS: u8 = 10;
+
+/// A list style (`text:list-style`), defining bullets or numbering for each level.
+pub struct ListStyle {
+    name: String,
+    pub display_name: Option<String>,
+    pub automatic: bool,
+    pub levels: Vec<ListLevel>,
+}
+
+impl ListStyle {
+    pub fn new<T: Into<String>>(name: T) -> Self {
+        Self {
+            name: name.into(),
+            display_name: None,
+            automatic: false,
+            levels: Vec::new(),
+        }
+    }
+
+    /// Helper to create a bulleted list style, cycling through the given bullet characters.
+    pub fn bullet<T: Into<String>>(name: T, bullet_chars: &[&str]) -> Self {
+        let mut style = ListStyle::new(name);
+        let chars = if bullet_chars.is_empty() { &["•"][..] } else { bullet_chars };
+        for level in 1..=LIST_LEVELS {
+            let bullet_char = chars[(level as usize - 1) % chars.len()];
+            style.levels.push(ListLevel::bullet(level, bullet_char));
+        }
+        style
+    }
+
+    /// Helper to create a numbered list style (e.g. `1`, `a`, `i`) with a `.` suffix.
+    pub fn numbered<T: Into<String>>(name: T, num_format: &str) -> Self {
+        let mut style = ListStyle::new(name);
+        for level in 1..=LIST_LEVELS {
+            let mut list_level = ListLevel::number(level, num_format);
+            list_level.num_suffix = Some(".".to_string());
+            style.levels.push(list_level);
+        }
+        style
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add (or replace) the definition of a level.
+    pub fn set_level(&mut self, level: ListLevel) {
+        self.levels.retain(|l| l.level != level.level);
+        self.levels.push(level);
+        self.levels.sort_by_key(|l| l.level);
+    }
+}
+
+impl FlatOdtXmlWrite for ListStyle {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let mut start = XmlWriterEvent::start_element("text:list-style")
+            .attr("style:name", &self.name);
+        start = self.add_optional_string_attributes(start);
+        writer.write(start)?;
+        for level in &self.levels {
+            level.write_flat_odt_xml(writer)?;
+        }
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
+        vec![
+            ("style:display-name", self.display_name.as_deref()),
+        ]
+    }
+}
+
+// ======================================================================================
+// Levels
+// ======================================================================================
+
+/// Label of a list level
+pub enum ListLabel {
+    Bullet(String),
+    Number {
+        /// `1`, `a`, `A`, `i`, `I` or empty (no number)
+        num_format: String,
+        /// Number of parent levels shown in the label (e.g. 3 for "1.2.3")
+        display_levels: Option<u8>,
+        start_value: Option<u32>,
+    },
+}
+
+/// Definition of a single level of a list (or outline) style.
+///
+/// Indentation uses the "label alignment" mode: the label starts at
+/// `margin_left + text_indent` and the text at `tab_stop_position`.
+pub struct ListLevel {
+    /// Level, from 1 to 10
+    pub level: u8,
+    pub label: ListLabel,
+    pub num_prefix: Option<String>,
+    pub num_suffix: Option<String>,
+    /// Character style of the label
+    pub text_style_name: Option<String>,
+    pub margin_left: Option<String>,
+    pub text_indent: Option<String>,
+    pub tab_stop_position: Option<String>,
+    /// `listtab`, `space` or `nothing`
+    pub label_followed_by: Option<String>,
+}
+
+impl ListLevel {
+    fn new(level: u8, label: ListLabel) -> Self {
+        let indent = Self::default_indent(level);
+        Self {
+            level,
+            label,
+            num_prefix: None,
+            num_suffix: None,
+            text_style_name: None,
+            margin_left: Some(indent.clone()),
+            text_indent: Some("-0.635cm".to_string()),
+            tab_stop_position: Some(indent),
+            label_followed_by: Some("listtab".to_string()),
+        }
+    }
+
+    /// Create a bullet level with the default indentation.
+    pub fn bullet(level: u8, bullet_char: &str) -> Self {
+        ListLevel::new(level, ListLabel::Bullet(bullet_char.to_string()))
+    }
+
+    /// Create a numbered level with the default indentation.
+    pub fn number(level: u8, num_format: &str) -> Self {
+        ListLevel::new(level, ListLabel::Number {
+            num_format: num_format.to_string(),
+            display_levels: None,
+            start_value: None,
+        })
+    }
+
+    /// Default indentation (0.635cm per level)
+    fn default_indent(level: u8) -> String {
+        let thousandths = 635 * level as u32;
+        format!("{}.{:03}cm", thousandths / 1000, thousandths % 1000)
+    }
+
+    /// Write the level using a given tag name (list and outline styles share the same structure).
+    pub(crate) fn write_level(&self, writer: &mut EventWriter<&mut dyn Write>, tag_name: Option<&str>) -> Result<()> {
+        let mut attrs: Vec<(&str, String)> = vec![("text:level", self.level.to_string())];
+        let default_tag_name = match self.label {
+            ListLabel::Bullet(ref bullet_char) => {
+                attrs.push(("text:bullet-char", bullet_char.clone()));
+                "text:list-level-style-bullet"
+            }
+            ListLabel::Number { ref num_format, ref display_levels, ref start_value } => {
+                attrs.push(("style:num-format", num_format.clone()));
+                if let Some(display_levels) = display_levels {
+                    attrs.push(("text:display-levels", display_levels.to_string()));
+                }
+                if let Some(start_value) = start_value {
+                    attrs.push(("text:start-value", start_value.to_string()));
+                }
+                "text:list-level-style-number"
+            }
+        };
+        let mut start = XmlWriterEvent::start_element(tag_name.unwrap_or(default_tag_name));
+        for (key, value) in &attrs {
+            start = start.attr(*key, value.as_str());
+        }
+        start = self.add_optional_string_attributes(start);
+        writer.write(start)?;
+
+        writer.write(
+            XmlWriterEvent::start_element("style:list-level-properties")
+                .attr("text:list-level-position-and-space-mode", "label-alignment")
+        )?;
+        let mut alignment = XmlWriterEvent::start_element("style:list-level-label-alignment");
+        for (key, value) in [
+            ("text:label-followed-by", self.label_followed_by.as_deref()),
+            ("text:list-tab-stop-position", self.tab_stop_position.as_deref()),
+            ("fo:text-indent", self.text_indent.as_deref()),
+            ("fo:margin-left", self.margin_left.as_deref()),
+        ] {
+            if let Some(v) = value {
+                alignment = alignment.attr(key, v);
+            }
+        }
+        writer.write(alignment)?;
+        writer.write(XmlWriterEvent::end_element())?; // label-alignment
+        writer.write(XmlWriterEvent::end_element())?; // level-properties
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl FlatOdtXmlWrite for ListLevel {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        self.write_level(writer, None)
+    }
+
+    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
+        vec![
+            ("style:num-prefix", self.num_prefix.as_deref()),
+            ("style:num-suffix", self.num_suffix.as_deref()),
+            ("text:style-name", self.text_style_name.as_deref()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xml::writer::EmitterConfig;
+    use super::*;
+
+    fn write<T: FlatOdtXmlWrite>(element: &T) -> Result<String> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut out as &mut dyn Write);
+        element.write_flat_odt_xml(&mut writer)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn helpers_define_every_level() {
+        let bullets = ListStyle::bullet("Bullets", &["•", "◦"]);
+        assert_eq!(bullets.levels.len(), LIST_LEVELS as usize);
+        let xml = write(&bullets).unwrap();
+        assert!(xml.contains("text:level=\"2\" text:bullet-char=\"◦\""), "{}", xml);
+        assert!(xml.contains("text:level=\"3\" text:bullet-char=\"•\""), "{}", xml);
+        let xml = write(&ListStyle::numbered("Numbers", "a")).unwrap();
+        assert_eq!(xml.matches("<text:list-level-style-number ").count(), LIST_LEVELS as usize);
+        assert!(xml.contains("text:level=\"10\" style:num-format=\"a\" style:num-suffix=\".\""), "{}", xml);
+        assert!(xml.contains("fo:margin-left=\"6.350cm\""), "{}", xml);
+    }
+
+    #[test]
+    fn levels_are_replaced_and_kept_in_order() {
+        let mut style = ListStyle::new("L");
+        style.set_level(ListLevel::number(3, "i"));
+        style.set_level(ListLevel::bullet(1, "-"));
+        style.set_level(ListLevel::number(3, "I"));
+        assert_eq!(style.levels.iter().map(|l| l.level).collect::<Vec<_>>(), [1, 3]);
+        let xml = write(&style).unwrap();
+        assert!(xml.contains("style:num-format=\"I\"") && !xml.contains("style:num-format=\"i\""), "{}", xml);
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::container::Container;
+use crate::error::Result;
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+
+/// A master page: a page layout plus its headers and footers.
+///
+/// The document always has a master page named `Standard` (built from `Document::header`,
+/// `Document::footer`, etc.). Additional master pages are started by paragraphs whose style
+/// sets `style:master-page-name` (see `Document::master_page_style`).
+pub struct MasterPage {
+    name: String,
+    pub page_layout_name: String,
+    pub display_name: Option<String>,
+    /// Master page used for the pages following the first one of this master
+    pub next_style_name: Option<String>,
+    pub header: Container,
+    pub header_left: Container,
+    pub header_first: Container,
+    pub footer: Container,
+    pub footer_left: Container,
+    pub footer_first: Container,
+}
+
+impl MasterPage {
+    pub fn new<T: Into<String>, U: Into<String>>(name: T, page_layout_name: U) -> Self {
+        Self {
+            name: name.into(),
+            page_layout_name: page_layout_name.into(),
+            display_name: None,
+            next_style_name: None,
+            header: Container::new(),
+            header_left: Container::new(),
+            header_first: Container::new(),
+            footer: Container::new(),
+            footer_left: Container::new(),
+            footer_first: Container::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl FlatOdtXmlWrite for MasterPage {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let mut start = XmlWriterEvent::start_element("style:master-page")
+            .attr("style:name", &self.name)
+            .attr("style:page-layout-name", &self.page_layout_name);
+        start = self.add_optional_string_attributes(start);
+        writer.write(start)?;
+        write_header_footer(writer, "style:header", &self.header)?;
+        write_header_footer(writer, "style:header-left", &self.header_left)?;
+        write_header_footer(writer, "style:header-first", &self.header_first)?;
+        write_header_footer(writer, "style:footer", &self.footer)?;
+        write_header_footer(writer, "style:footer-left", &self.footer_left)?;
+        write_header_footer(writer, "style:footer-first", &self.footer_first)?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
+        vec![
+            ("style:display-name", self.display_name.as_deref()),
+            ("style:next-style-name", self.next_style_name.as_deref()),
+        ]
+    }
+}
+
+/// Write a header/footer element of a master page, skipping empty containers.
+pub(crate) fn write_header_footer(
+    writer: &mut EventWriter<&mut dyn Write>,
+    tag_name: &str,
+    content: &Container,
+) -> Result<()> {
+    if !content.is_empty() {
+        writer.write(XmlWriterEvent::start_element(tag_name))?;
+        content.write_flat_odt_xml(writer)?;
+        writer.write(XmlWriterEvent::end_element())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,257 @@
+//! Mail merge: replacement of `{{field}}` placeholders in the text of a generated document
+//!
+//! Placeholders are searched in the concatenated text of each paragraph or heading, so a
+//! placeholder split across several `text:span` runs (as LibreOffice often does) is still
+//! found. The value is written in the run where the placeholder starts, and the remaining
+//! characters of the placeholder are removed from the following runs.
+
+use std::collections::HashMap;
+use std::io::Write;
+use serde::Serialize;
+use serde_yaml_ng::Value;
+use xml::name::OwnedName;
+use xml::reader::{EventReader, ParserConfig, XmlEvent as XmlReaderEvent};
+use xml::writer::EmitterConfig;
+use crate::error::{Error, Result};
+use crate::fodt_parser::TEXT_NS;
+
+/// Values of the placeholders, by field name
+///
+/// Nested values of serialized records are flattened with dotted names
+/// (e.g. `{{customer.name}}`, `{{items.0}}`).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MergeData {
+    values: HashMap<String, String>,
+}
+
+impl MergeData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the values from any serializable record (struct, map...).
+    pub fn from_serialize<T: Serialize + ?Sized>(record: &T) -> Result<Self> {
+        let value = serde_yaml_ng::to_value(record)
+            .map_err(|e| Error::Template(format!("Invalid merge record: {}", e)))?;
+        let mut data = MergeData::new();
+        data.flatten("", value);
+        Ok(data)
+    }
+
+    fn flatten(&mut self, name: &str, value: Value) {
+        let child_name = |key: &str| if name.is_empty() { key.to_string() } else { format!("{}.{}", name, key) };
+        match value {
+            Value::Mapping(mapping) => {
+                for (k, v) in mapping {
+                    let key = match k {
+                        Value::String(s) => s,
+                        Value::Number(n) => n.to_string(),
+                        Value::Bool(b) => b.to_string(),
+                        _ => continue,
+                    };
+                    self.flatten(&child_name(&key), v);
+                }
+            }
+            Value::Sequence(sequence) => {
+                for (i, v) in sequence.into_iter().enumerate() {
+                    self.flatten(&child_name(&i.to_string()), v);
+                }
+            }
+            Value::Tagged(tagged) => self.flatten(name, tagged.value),
+            Value::String(s) => self.insert(name, s),
+            Value::Number(n) => self.insert(name, n.to_string()),
+            Value::Bool(b) => self.insert(name, b.to_string()),
+            Value::Null => self.insert(name, ""),
+        }
+    }
+
+    /// Set the value of a field.
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// Get the value of a field.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Replace the placeholders of a Flat ODT document.
+    pub(crate) fn substitute(&self, flat: &[u8], out: &mut dyn Write) -> Result<()> {
+        let mut reader = EventReader::new_with_config(
+            flat,
+            ParserConfig::new()
+                .ignore_comments(false)
+        );
+        let mut writer = EmitterConfig::new().create_writer(out);
+        //Events of the paragraph being read
+        let mut paragraph: Vec<XmlReaderEvent> = Vec::new();
+        let mut depth = 0usize;
+        loop {
+            let ev = reader.next()?;
+            match ev {
+                XmlReaderEvent::EndDocument => break,
+                XmlReaderEvent::StartElement { ref name, .. } if depth > 0 || is_paragraph(name) => depth += 1,
+                XmlReaderEvent::EndElement { .. } if depth > 0 => depth -= 1,
+                _ => {}
+            }
+            if depth > 0 || !paragraph.is_empty() {
+                paragraph.push(ev);
+                if depth == 0 {
+                    self.substitute_paragraph(&mut paragraph);
+                    for ev in paragraph.drain(..) {
+                        write_event(&mut writer, &ev)?;
+                    }
+                }
+                continue;
+            }
+            match ev {
+                XmlReaderEvent::Characters(text) => {
+                    let mut events = vec![XmlReaderEvent::Characters(text)];
+                    self.substitute_paragraph(&mut events);
+                    write_event(&mut writer, &events[0])?;
+                }
+                other => write_event(&mut writer, &other)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the placeholders found in the concatenated text of the events.
+    fn substitute_paragraph(&self, events: &mut [XmlReaderEvent]) {
+        let mut text = String::new();
+        for ev in events.iter() {
+            if let XmlReaderEvent::Characters(t) | XmlReaderEvent::Whitespace(t) = ev {
+                text.push_str(t);
+            }
+        }
+        //Byte ranges of the placeholders and their values
+        let mut replacements: Vec<(usize, usize, &str)> = Vec::new();
+        let mut position = 0;
+        while let Some(start) = text[position..].find("{{").map(|i| i + position) {
+            let Some(end) = text[start + 2..].find("}}").map(|i| i + start + 4) else { break };
+            match self.get(text[start + 2..end - 2].trim()) {
+                Some(value) => {
+                    replacements.push((start, end, value));
+                    position = end;
+                }
+                None => position = start + 2,
+            }
+        }
+        if replacements.is_empty() {
+            return;
+        }
+
+        let mut offset = 0;
+        for ev in events.iter_mut() {
+            let (XmlReaderEvent::Characters(t) | XmlReaderEvent::Whitespace(t)) = ev else { continue };
+            let mut replaced = String::new();
+            for (i, c) in t.char_indices() {
+                let global = offset + i;
+                match replacements.iter().find(|(start, end, _)| *start <= global && global < *end) {
+                    Some((start, _, value)) if *start == global => replaced.push_str(value),
+                    Some(_) => {}
+                    None => replaced.push(c),
+                }
+            }
+            offset += t.len();
+            *t = replaced;
+        }
+    }
+}
+
+impl Serialize for MergeData {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.values.serialize(serializer)
+    }
+}
+
+impl From<HashMap<String, String>> for MergeData {
+    fn from(values: HashMap<String, String>) -> Self {
+        Self { values }
+    }
+}
+
+/// Paragraphs and headings, which text is searched as a whole
+fn is_paragraph(name: &OwnedName) -> bool {
+    name.namespace.as_deref() == Some(TEXT_NS) && (name.local_name == "p" || name.local_name == "h")
+}
+
+fn write_event<W: Write>(writer: &mut xml::EventWriter<W>, ev: &XmlReaderEvent) -> Result<()> {
+    if let Some(ev) = ev.as_writer_event() {
+        writer.write(ev)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use crate::paragraph::Paragraph;
+    use crate::text_span::TextSpan;
+
+    fn substitute(data: &MergeData, paragraph: &str) -> String {
+        let flat = format!("<text:p xmlns:text=\"{}\">{}</text:p>", TEXT_NS, paragraph);
+        let mut out = Vec::new();
+        data.substitute(flat.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn data(name: &str) -> MergeData {
+        let mut data = MergeData::new();
+        data.insert("name", name);
+        data
+    }
+
+    #[test]
+    fn fields_split_across_spans_are_replaced() {
+        let merged = substitute(&data("Alice"), "<text:span>Dear {{na</text:span><text:span>me}}!</text:span>");
+        assert!(merged.contains("<text:span>Dear Alice</text:span><text:span>!</text:span>"), "{}", merged);
+    }
+
+    #[test]
+    fn unknown_fields_are_left_as_is() {
+        let merged = substitute(&data("Alice"), "{{unknown}} and {{ name }}");
+        assert!(merged.contains(">{{unknown}} and Alice<"), "{}", merged);
+    }
+
+    #[test]
+    fn nested_values_have_dotted_names() {
+        #[derive(Serialize)]
+        struct Customer {
+            name: &'static str,
+            vip: bool,
+        }
+        #[derive(Serialize)]
+        struct Order {
+            customer: Customer,
+            items: Vec<u32>,
+            note: Option<String>,
+        }
+        let order = Order { customer: Customer { name: "Alice", vip: true }, items: vec![3, 5], note: None };
+        let data = MergeData::from_serialize(&order).unwrap();
+        assert_eq!(data.get("customer.name"), Some("Alice"));
+        assert_eq!(data.get("customer.vip"), Some("true"));
+        assert_eq!(data.get("items.1"), Some("5"));
+        assert_eq!(data.get("note"), Some(""));
+        assert_eq!(data.get("customer"), None);
+    }
+
+    #[test]
+    fn merge_records_gives_a_document_per_record() {
+        let mut doc = Document::new();
+        let mut paragraph = Paragraph::new();
+        paragraph.content.add(TextSpan::from_text("Hello {{"));
+        paragraph.content.add(TextSpan::from_text("name}}"));
+        doc.body.add(paragraph);
+        let records = ["Alice", "Bob", "Carol"].map(|name| HashMap::from([("name", name)]));
+        let merged: Vec<String> = doc.merge_records(records).unwrap()
+            .map(|flat| String::from_utf8(flat.unwrap()).unwrap())
+            .collect();
+        assert_eq!(merged.len(), 3);
+        for (flat, name) in merged.iter().zip(["Alice", "Bob", "Carol"]) {
+            assert!(flat.contains(&format!("Hello {}", name)), "{}", flat);
+            assert!(!flat.contains("{{"));
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::Write;
+use std::time::Duration;
+use chrono::{DateTime, Local, NaiveDateTime};
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::error::Result;
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Document metadata (`office:meta`)
+pub struct Metadata {
+    pub title: Option<String>,
+    pub subject: Option<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    /// Author of the document
+    pub initial_creator: Option<String>,
+    /// Author of the last modification
+    pub creator: Option<String>,
+    /// Language tag (e.g. `en-US`)
+    pub language: Option<String>,
+    /// Creation date (defaults to the generation time, see `Metadata::generation_date`)
+    pub creation_date: Option<NaiveDateTime>,
+    /// Last modification date (defaults to the creation date)
+    pub modification_date: Option<NaiveDateTime>,
+    pub editing_cycles: Option<u32>,
+    pub generator: String,
+    /// Custom properties (`meta:user-defined`)
+    pub user_defined: Vec<(String, MetaValue)>,
+}
+
+/// Typed value of a custom property
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetaValue {
+    String(String),
+    Float(f64),
+    Boolean(bool),
+    Date(NaiveDateTime),
+    Time(Duration),
+}
+
+impl MetaValue {
+    fn value_type(&self) -> &'static str {
+        match self {
+            MetaValue::String(_) => "string",
+            MetaValue::Float(_) => "float",
+            MetaValue::Boolean(_) => "boolean",
+            MetaValue::Date(_) => "date",
+            MetaValue::Time(_) => "time",
+        }
+    }
+
+    fn to_odf_string(&self) -> String {
+        match self {
+            MetaValue::String(s) => s.clone(),
+            MetaValue::Float(f) => f.to_string(),
+            MetaValue::Boolean(b) => b.to_string(),
+            MetaValue::Date(d) => d.format(DATE_FORMAT).to_string(),
+            MetaValue::Time(t) => format_duration(t),
+        }
+    }
+}
+
+/// Format a duration as ISO 8601 (`PT1H30M0S`)
+pub(crate) fn format_duration(duration: &Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("PT{}H{}M{}S", seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+}
+
+/// Parse an ISO 8601 duration (`PT1H30M0S`), as written by `format_duration`.
+pub(crate) fn parse_duration(text: &str) -> Option<Duration> {
+    let mut rest = text.trim().strip_prefix("PT")?;
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let end = rest.find(|c: char| c.is_ascii_alphabetic())?;
+        let value: f64 = rest[..end].parse().ok()?;
+        seconds += value * match &rest[end..end + 1] {
+            "H" => 3600.0,
+            "M" => 60.0,
+            "S" => 1.0,
+            _ => return None,
+        };
+        rest = &rest[end + 1..];
+    }
+    Some(Duration::from_secs_f64(seconds))
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self {
+            title: None,
+            subject: None,
+            description: None,
+            keywords: Vec::new(),
+            initial_creator: None,
+            creator: None,
+            language: None,
+            creation_date: None,
+            modification_date: None,
+            editing_cycles: None,
+            generator: format!("odtgen/{}", env!("CARGO_PKG_VERSION")),
+            user_defined: Vec::new(),
+        }
+    }
+
+    /// Add (or replace) a custom property.
+    pub fn set_user_defined<T: Into<String>>(&mut self, name: T, value: MetaValue) {
+        let name = name.into();
+        self.user_defined.retain(|(n, _)| *n != name);
+        self.user_defined.push((name, value));
+    }
+
+    /// Date used when no creation date is set: the `SOURCE_DATE_EPOCH` environment variable
+    /// (seconds since the Unix epoch, as UTC) when set, for reproducible output, or the current
+    /// local time.
+    pub fn generation_date() -> NaiveDateTime {
+        std::env::var("SOURCE_DATE_EPOCH").ok()
+            .and_then(|epoch| Metadata::epoch_date(&epoch))
+            .unwrap_or_else(|| Local::now().naive_local())
+    }
+
+    /// UTC date of a `SOURCE_DATE_EPOCH` value, if valid
+    fn epoch_date(epoch: &str) -> Option<NaiveDateTime> {
+        epoch.trim().parse::<i64>().ok()
+            .and_then(|epoch| DateTime::from_timestamp(epoch, 0))
+            .map(|date| date.naive_utc())
+    }
+
+    /// Write a simple element with text content
+    fn write_text_element(writer: &mut EventWriter<&mut dyn Write>, tag_name: &str, text: &str) -> Result<()> {
+        writer.write(XmlWriterEvent::start_element(tag_name))?;
+        writer.write(XmlWriterEvent::characters(text))?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatOdtXmlWrite for Metadata {
+    /// Write the children of `office:meta`.
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let creation_date = self.creation_date.unwrap_or_else(Metadata::generation_date);
+        let modification_date = self.modification_date.unwrap_or(creation_date);
+
+        Metadata::write_text_element(writer, "meta:generator", &self.generator)?;
+        for (tag_name, value) in [
+            ("dc:title", self.title.as_deref()),
+            ("dc:subject", self.subject.as_deref()),
+            ("dc:description", self.description.as_deref()),
+        ] {
+            if let Some(v) = value {
+                Metadata::write_text_element(writer, tag_name, v)?;
+            }
+        }
+        for keyword in &self.keywords {
+            Metadata::write_text_element(writer, "meta:keyword", keyword)?;
+        }
+        if let Some(ref initial_creator) = self.initial_creator {
+            Metadata::write_text_element(writer, "meta:initial-creator", initial_creator)?;
+        }
+        if let Some(ref creator) = self.creator {
+            Metadata::write_text_element(writer, "dc:creator", creator)?;
+        }
+        Metadata::write_text_element(writer, "meta:creation-date", &creation_date.format(DATE_FORMAT).to_string())?;
+        Metadata::write_text_element(writer, "dc:date", &modification_date.format(DATE_FORMAT).to_string())?;
+        if let Some(ref editing_cycles) = self.editing_cycles {
+            Metadata::write_text_element(writer, "meta:editing-cycles", &editing_cycles.to_string())?;
+        }
+        if let Some(ref language) = self.language {
+            Metadata::write_text_element(writer, "dc:language", language)?;
+        }
+        for (name, value) in &self.user_defined {
+            writer.write(
+                XmlWriterEvent::start_element("meta:user-defined")
+                    .attr("meta:name", name)
+                    .attr("meta:value-type", value.value_type())
+            )?;
+            writer.write(XmlWriterEvent::characters(&value.to_odf_string()))?;
+            writer.write(XmlWriterEvent::end_element())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use xml::writer::EmitterConfig;
+    use super::*;
+
+    fn write(metadata: &Metadata) -> String {
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut out as &mut dyn Write);
+        metadata.write_flat_odt_xml(&mut writer).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn date(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 2, 29).unwrap().and_hms_opt(hour, 5, 0).unwrap()
+    }
+
+    #[test]
+    fn user_defined_values_are_typed() {
+        let mut metadata = Metadata::new();
+        metadata.creation_date = Some(date(8));
+        metadata.set_user_defined("Reviewer", MetaValue::String("Alice".to_string()));
+        metadata.set_user_defined("Version", MetaValue::Float(1.5));
+        metadata.set_user_defined("Approved", MetaValue::Boolean(true));
+        metadata.set_user_defined("Deadline", MetaValue::Date(date(17)));
+        metadata.set_user_defined("Effort", MetaValue::Time(Duration::from_secs(5400)));
+        metadata.set_user_defined("Version", MetaValue::Float(2.0));
+        let xml = write(&metadata);
+        for expected in [
+            "<meta:user-defined meta:name=\"Reviewer\" meta:value-type=\"string\">Alice</meta:user-defined>",
+            "<meta:user-defined meta:name=\"Approved\" meta:value-type=\"boolean\">true</meta:user-defined>",
+            "<meta:user-defined meta:name=\"Deadline\" meta:value-type=\"date\">2024-02-29T17:05:00</meta:user-defined>",
+            "<meta:user-defined meta:name=\"Effort\" meta:value-type=\"time\">PT1H30M0S</meta:user-defined>",
+            "<meta:user-defined meta:name=\"Version\" meta:value-type=\"float\">2</meta:user-defined>",
+        ] {
+            assert!(xml.contains(expected), "{} not in {}", expected, xml);
+        }
+        assert_eq!(xml.matches("meta:name=\"Version\"").count(), 1);
+        assert!(xml.find("Effort").unwrap() < xml.find("Version").unwrap());
+        assert_eq!(parse_duration("PT1H30M0S"), Some(Duration::from_secs(5400)));
+    }
+
+    #[test]
+    fn modification_date_defaults_to_the_creation_date() {
+        let mut metadata = Metadata::new();
+        metadata.creation_date = Some(date(8));
+        let xml = write(&metadata);
+        assert!(xml.contains("<meta:creation-date>2024-02-29T08:05:00</meta:creation-date>"), "{}", xml);
+        assert!(xml.contains("<dc:date>2024-02-29T08:05:00</dc:date>"), "{}", xml);
+        metadata.modification_date = Some(date(9));
+        assert!(write(&metadata).contains("<dc:date>2024-02-29T09:05:00</dc:date>"));
+    }
+
+    #[test]
+    fn source_date_epoch_is_read_as_utc_seconds() {
+        assert_eq!(Metadata::epoch_date("1709193900"), Some(date(8)));
+        assert_eq!(Metadata::epoch_date(" 0\n"), DateTime::from_timestamp(0, 0).map(|d| d.naive_utc()));
+        assert_eq!(Metadata::epoch_date("2024-02-29"), None);
+        assert_eq!(Metadata::epoch_date(""), None);
+        assert_eq!(Metadata::epoch_date("99999999999999999"), None);
+    }
+}
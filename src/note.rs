@@ -0,0 +1,313 @@
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use strum_macros::{AsRefStr, EnumString};
+use crate::container::Container;
+use crate::error::Result;
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::paragraph::Paragraph;
+use crate::statistics::StatisticsWalker;
+use crate::stylesheet::Stylesheet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, AsRefStr)]
+#[strum(serialize_all = "kebab-case")]
+pub enum NoteClass {
+    Footnote,
+    Endnote,
+}
+
+/// Automatic numbering of the notes of a document, in the order they are written.
+///
+/// Computed before writing the document (see `FlatOdtXmlWrite::number_notes`), following the
+/// number formats and start values of the notes configurations.
+pub struct NoteNumbering {
+    /// Number of notes, used for the identifiers
+    id: u32,
+    footnotes: u32,
+    endnotes: u32,
+    footnote_format: String,
+    footnote_start: u32,
+    endnote_format: String,
+    endnote_start: u32,
+}
+
+impl NoteNumbering {
+    pub fn new(footnotes: &NotesConfiguration, endnotes: &NotesConfiguration) -> Self {
+        Self {
+            id: 0,
+            footnotes: 0,
+            endnotes: 0,
+            footnote_format: footnotes.num_format.clone(),
+            footnote_start: footnotes.start_value,
+            endnote_format: endnotes.num_format.clone(),
+            endnote_start: endnotes.start_value,
+        }
+    }
+
+    /// Identifier and citation of the next note.
+    fn next(&mut self, note: &Note) -> NoteCitation {
+        self.id += 1;
+        let (prefix, count, format, start) = match note.note_class {
+            NoteClass::Footnote => ("ftn", &mut self.footnotes, &self.footnote_format, self.footnote_start),
+            NoteClass::Endnote => ("edn", &mut self.endnotes, &self.endnote_format, self.endnote_start),
+        };
+        let text = match note.label {
+            Some(ref label) => label.clone(),
+            None => {
+                *count += 1;
+                format_number(start + *count, format)
+            }
+        };
+        NoteCitation { id: format!("{}{}", prefix, self.id), text }
+    }
+}
+
+/// Number written in a number format (`1`, `a`, `A`, `i`, `I`, decimal for the others).
+fn format_number(number: u32, format: &str) -> String {
+    match format {
+        "a" | "A" if number > 0 => {
+            //a, b... z, aa, bb... as LibreOffice does
+            let letter = (b'a' + ((number - 1) % 26) as u8) as char;
+            let text = letter.to_string().repeat(((number - 1) / 26 + 1) as usize);
+            if format == "A" { text.to_uppercase() } else { text }
+        }
+        "i" | "I" if number > 0 => {
+            let text = roman_number(number);
+            if format == "i" { text.to_lowercase() } else { text }
+        }
+        _ => number.to_string(),
+    }
+}
+
+fn roman_number(mut number: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+        (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+    ];
+    let mut text = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            text.push_str(numeral);
+            number -= value;
+        }
+    }
+    text
+}
+
+/// Identifier and citation text of a numbered note
+struct NoteCitation {
+    id: String,
+    text: String,
+}
+
+/// A footnote or endnote (`text:note`), placed inside a paragraph.
+pub struct Note {
+    pub note_class: NoteClass,
+    /// Custom citation label. Notes without a label are numbered automatically.
+    pub label: Option<String>,
+    /// Paragraphs of the note
+    pub body: Container,
+    /// Citation computed by `number_notes`
+    citation: Option<NoteCitation>,
+}
+
+impl Note {
+    pub fn new(note_class: NoteClass) -> Self {
+        Self {
+            note_class,
+            label: None,
+            body: Container::new(),
+            citation: None,
+        }
+    }
+
+    /// Helper to create a footnote with a single paragraph (`Footnote` style).
+    pub fn footnote<T: Into<String>>(text: T) -> Self {
+        let mut note = Note::new(NoteClass::Footnote);
+        note.body.add(Paragraph::from_text_and_style(text, "Footnote"));
+        note
+    }
+
+    /// Helper to create an endnote with a single paragraph (`Endnote` style).
+    pub fn endnote<T: Into<String>>(text: T) -> Self {
+        let mut note = Note::new(NoteClass::Endnote);
+        note.body.add(Paragraph::from_text_and_style(text, "Endnote"));
+        note
+    }
+}
+
+impl FlatOdtXmlWrite for Note {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        //A note written without being numbered (e.g. on its own) is the first one
+        let citation = match self.citation {
+            Some(ref citation) => citation,
+            None => &NoteNumbering::new(
+                &NotesConfiguration::new(NoteClass::Footnote),
+                &NotesConfiguration::new(NoteClass::Endnote),
+            ).next(self),
+        };
+        writer.write(
+            XmlWriterEvent::start_element("text:note")
+                .attr("text:id", &citation.id)
+                .attr("text:note-class", self.note_class.as_ref())
+        )?;
+        let mut start = XmlWriterEvent::start_element("text:note-citation");
+        if let Some(ref label) = self.label {
+            start = start.attr("text:label", label);
+        }
+        writer.write(start)?;
+        writer.write(XmlWriterEvent::characters(&citation.text))?;
+        writer.write(XmlWriterEvent::end_element())?;
+        writer.write(XmlWriterEvent::start_element("text:note-body"))?;
+        self.body.write_flat_odt_xml(writer)?;
+        writer.write(XmlWriterEvent::end_element())?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        self.body.update_statistics(statistics);
+    }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        self.citation = Some(numbering.next(self));
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
+        self.body.register_automatic_styles(stylesheet);
+    }
+}
+
+// ======================================================================================
+// Configuration
+// ======================================================================================
+
+/// Where the numbering of footnotes restarts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, AsRefStr)]
+#[strum(serialize_all = "kebab-case")]
+pub enum NoteRestart {
+    Document,
+    Chapter,
+    Page,
+}
+
+/// Numbering and styles of a note class (`text:notes-configuration`).
+pub struct NotesConfiguration {
+    pub note_class: NoteClass,
+    /// `1`, `a`, `A`, `i`, `I`...
+    pub num_format: String,
+    pub num_prefix: Option<String>,
+    pub num_suffix: Option<String>,
+    /// Offset added to the note numbers (0 starts at 1)
+    pub start_value: u32,
+    /// Restart of the numbering (footnotes only)
+    pub start_numbering_at: Option<NoteRestart>,
+    /// Paragraph style of the note body
+    pub default_style_name: Option<String>,
+    /// Character style of the citation in the text
+    pub citation_body_style_name: Option<String>,
+    /// Character style of the citation in the note body
+    pub citation_style_name: Option<String>,
+}
+
+impl NotesConfiguration {
+    pub fn new(note_class: NoteClass) -> Self {
+        let (num_format, start_numbering_at, default_style, anchor_style) = match note_class {
+            NoteClass::Footnote => ("1", Some(NoteRestart::Document), "Footnote", "FootnoteAnchor"),
+            NoteClass::Endnote => ("i", None, "Endnote", "EndnoteAnchor"),
+        };
+        Self {
+            note_class,
+            num_format: num_format.to_string(),
+            num_prefix: None,
+            num_suffix: None,
+            start_value: 0,
+            start_numbering_at,
+            default_style_name: Some(default_style.to_string()),
+            citation_body_style_name: Some(anchor_style.to_string()),
+            citation_style_name: None,
+        }
+    }
+}
+
+impl FlatOdtXmlWrite for NotesConfiguration {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let start_value = self.start_value.to_string();
+        let mut start = XmlWriterEvent::start_element("text:notes-configuration")
+            .attr("text:note-class", self.note_class.as_ref())
+            .attr("style:num-format", &self.num_format)
+            .attr("text:start-value", &start_value);
+        start = self.add_optional_string_attributes(start);
+        if self.note_class == NoteClass::Footnote {
+            start = start.attr("text:footnotes-position", "page");
+            if let Some(ref restart) = self.start_numbering_at {
+                start = start.attr("text:start-numbering-at", restart.as_ref());
+            }
+        }
+        writer.write(start)?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
+        vec![
+            ("style:num-prefix", self.num_prefix.as_deref()),
+            ("style:num-suffix", self.num_suffix.as_deref()),
+            ("text:default-style-name", self.default_style_name.as_deref()),
+            ("text:citation-body-style-name", self.citation_body_style_name.as_deref()),
+            ("text:citation-style-name", self.citation_style_name.as_deref()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::document::Document;
+    use super::*;
+
+    fn generate(doc: &mut Document) -> String {
+        let mut out = Vec::new();
+        doc.generate_fodt(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Texts of the note citations of a generated document
+    fn citations(flat: &str) -> Vec<&str> {
+        flat.split("<text:note-citation").skip(1)
+            .map(|s| &s[s.find('>').unwrap() + 1..s.find('<').unwrap()])
+            .collect()
+    }
+
+    #[test]
+    fn numbers_follow_the_num_format() {
+        assert_eq!(format_number(4, "1"), "4");
+        assert_eq!(format_number(2, "a"), "b");
+        assert_eq!(format_number(28, "A"), "BB");
+        assert_eq!(format_number(14, "i"), "xiv");
+        assert_eq!(format_number(1994, "I"), "MCMXCIV");
+        assert_eq!(format_number(0, "i"), "0");
+    }
+
+    #[test]
+    fn notes_are_numbered_per_class_with_the_configuration() {
+        let mut doc = Document::new();
+        doc.footnotes_configuration.start_value = 4;
+        let mut par = Paragraph::from_text("Text");
+        par.content.add(Note::footnote("First"));
+        par.content.add(Note::endnote("Second"));
+        let mut labelled = Note::footnote("Third");
+        labelled.label = Some("*".to_string());
+        par.content.add(labelled);
+        par.content.add(Note::footnote("Fourth"));
+        par.content.add(Note::endnote("Fifth"));
+        doc.body.add(par);
+
+        //Generating the document again gives the same numbers
+        for flat in [generate(&mut doc), generate(&mut doc)] {
+            assert_eq!(citations(&flat), ["5", "i", "*", "6", "ii"]);
+            for id in ["ftn1", "edn2", "ftn3", "ftn4", "edn5"] {
+                assert!(flat.contains(&format!("text:id=\"{}\"", id)), "{}", id);
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::error::{Error, Result};
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::list_style::{ListLabel, ListLevel, LIST_LEVELS};
+
+/// Numbering of headings (`text:outline-style`).
+///
+/// Levels use the same definition as list styles, but only numbered labels are allowed.
+pub struct OutlineStyle {
+    pub levels: Vec<ListLevel>,
+}
+
+impl OutlineStyle {
+    /// Create an outline style without numbering.
+    pub fn new() -> Self {
+        OutlineStyle::with_format("", false)
+    }
+
+    /// Helper to create a "1.2.3" style numbering for all levels.
+    pub fn numbered() -> Self {
+        OutlineStyle::with_format("1", true)
+    }
+
+    fn with_format(num_format: &str, show_parents: bool) -> Self {
+        let mut levels = Vec::new();
+        for level in 1..=LIST_LEVELS {
+            let mut outline_level = ListLevel::number(level, num_format);
+            if show_parents {
+                outline_level.label = ListLabel::Number {
+                    num_format: num_format.to_string(),
+                    display_levels: Some(level),
+                    start_value: None,
+                };
+            }
+            outline_level.margin_left = None;
+            outline_level.text_indent = None;
+            outline_level.tab_stop_position = None;
+            levels.push(outline_level);
+        }
+        Self { levels }
+    }
+
+    /// Add (or replace) the definition of a level.
+    pub fn set_level(&mut self, level: ListLevel) {
+        self.levels.retain(|l| l.level != level.level);
+        self.levels.push(level);
+        self.levels.sort_by_key(|l| l.level);
+    }
+}
+
+impl Default for OutlineStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatOdtXmlWrite for OutlineStyle {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        writer.write(
+            XmlWriterEvent::start_element("text:outline-style")
+                .attr("style:name", "Outline")
+        )?;
+        for level in &self.levels {
+            if let ListLabel::Bullet(_) = level.label {
+                return Err(Error::stylesheet("Outline", "bullet-char", "Outline levels cannot use bullets!"));
+            }
+            level.write_level(writer, Some("text:outline-level-style"))?;
+        }
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,566 @@
+//! Packaged ODT (ZIP) input and output
+//!
+//! The package is produced by splitting an already generated Flat ODT into the parts required
+//! by the OpenDocument package format. Embedded images (`office:binary-data`) are decoded and
+//! stored as `Pictures/*` entries.
+//!
+//! Packages (e.g. `.ott` templates) are read by the reverse process: the parts are merged into
+//! a single Flat ODT and the pictures are embedded as `office:binary-data`.
+
+use std::collections::HashSet;
+use std::io::{Cursor, Read, Write};
+use base64::prelude::*;
+use xml::name::OwnedName;
+use xml::namespace::Namespace;
+use xml::reader::{EventReader, ParserConfig, XmlEvent as REvent};
+use xml::writer::{EventWriter, XmlEvent as WEvent, EmitterConfig};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use crate::error::{Error, Result};
+
+const MIMETYPE: &str = "application/vnd.oasis.opendocument.text";
+
+/// Parts of the package generated from the top-level elements of a Flat ODT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Content,
+    Styles,
+    Meta,
+    Settings,
+}
+
+impl Part {
+    const ALL: [Part; 4] = [Part::Content, Part::Styles, Part::Meta, Part::Settings];
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Part::Content => "content.xml",
+            Part::Styles => "styles.xml",
+            Part::Meta => "meta.xml",
+            Part::Settings => "settings.xml",
+        }
+    }
+
+    fn root_element(&self) -> &'static str {
+        match self {
+            Part::Content => "office:document-content",
+            Part::Styles => "office:document-styles",
+            Part::Meta => "office:document-meta",
+            Part::Settings => "office:document-settings",
+        }
+    }
+
+    /// Parts receiving a given top-level element of the flat document.
+    fn targets(element: &str) -> &'static [Part] {
+        match element {
+            "office:meta" => &[Part::Meta],
+            "office:settings" => &[Part::Settings],
+            "office:scripts" | "office:body" => &[Part::Content],
+            "office:styles" | "office:master-styles" => &[Part::Styles],
+            "office:font-face-decls" | "office:automatic-styles" => &[Part::Styles, Part::Content],
+            _ => &[],
+        }
+    }
+}
+
+/// An image extracted from the flat document
+struct Picture {
+    path: String,
+    media_type: String,
+    data: Vec<u8>,
+}
+
+/// Convert a Flat ODT document into an ODT package (ZIP).
+pub(crate) fn write_odt(flat: &[u8], out: &mut dyn Write) -> Result<()> {
+    let mut reader = EventReader::new(flat);
+    let mut parts: Vec<(Part, EventWriter<Vec<u8>>)> = Part::ALL.iter()
+        .map(|part| (*part, EmitterConfig::new().create_writer(Vec::new())))
+        .collect();
+    let mut pictures: Vec<Picture> = Vec::new();
+    let mut targets: &[Part] = &[];
+    let mut depth = 0usize;
+
+    loop {
+        let ev = reader.next()?;
+        match ev {
+            REvent::EndDocument => break,
+            REvent::StartDocument { .. } | REvent::ProcessingInstruction { .. } | REvent::Doctype { .. } => {}
+            REvent::StartElement { ref name, ref attributes, ref namespace } if depth == 0 => {
+                if name.local_name != "document" {
+                    return Err(Error::Xml(format!("Unexpected root element: {}", name)));
+                }
+                let version = attributes.iter()
+                    .find(|a| a.name.local_name == "version")
+                    .map(|a| a.value.as_str())
+                    .unwrap_or("1.3");
+                for (part, writer) in parts.iter_mut() {
+                    write_root(writer, part.root_element(), version, namespace)?;
+                }
+                depth += 1;
+            }
+            REvent::StartElement { ref name, ref attributes, .. } => {
+                if depth == 1 {
+                    targets = Part::targets(&qualified(name));
+                }
+                depth += 1;
+                if qualified(name) == "draw:image" {
+                    let (picture, children) = read_binary_data(&mut reader, attributes, pictures.len() + 1)?;
+                    let mut start = WEvent::start_element("draw:image");
+                    for attr in attributes.iter() {
+                        start = start.attr(attr.name.borrow(), &attr.value);
+                    }
+                    if let Some(ref picture) = picture {
+                        start = start
+                            .attr("xlink:href", &picture.path)
+                            .attr("xlink:type", "simple")
+                            .attr("xlink:show", "embed")
+                            .attr("xlink:actuate", "onLoad");
+                    }
+                    let start: WEvent = start.into();
+                    // The end of draw:image was consumed together with the binary data
+                    for (_, writer) in parts.iter_mut().filter(|(p, _)| targets.contains(p)) {
+                        writer.write(start.clone())?;
+                        for child in &children {
+                            if let Some(child) = child.as_writer_event() {
+                                writer.write(child)?;
+                            }
+                        }
+                        writer.write(WEvent::end_element())?;
+                    }
+                    pictures.extend(picture);
+                    depth -= 1;
+                    continue;
+                }
+                write_to_targets(&mut parts, targets, &ev)?;
+            }
+            REvent::EndElement { .. } => {
+                depth -= 1;
+                if depth == 0 {
+                    for (_, writer) in parts.iter_mut() {
+                        writer.write(WEvent::end_element())?;
+                    }
+                } else {
+                    write_to_targets(&mut parts, targets, &ev)?;
+                    if depth == 1 {
+                        targets = &[];
+                    }
+                }
+            }
+            other => {
+                if depth > 1 {
+                    write_to_targets(&mut parts, targets, &other)?;
+                }
+            }
+        }
+    }
+
+    let mut buffer = Cursor::new(Vec::new());
+    let mut zip = ZipWriter::new(&mut buffer);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(MIMETYPE.as_bytes())?;
+    for (part, writer) in parts {
+        zip.start_file(part.file_name(), deflated)?;
+        zip.write_all(&writer.into_inner())?;
+    }
+    for picture in &pictures {
+        zip.start_file(picture.path.as_str(), stored)?;
+        zip.write_all(&picture.data)?;
+    }
+    zip.start_file("META-INF/manifest.xml", deflated)?;
+    zip.write_all(&manifest(&pictures)?)?;
+    zip.finish()?;
+
+    out.write_all(buffer.get_ref())?;
+    Ok(())
+}
+
+/// Qualified name (`prefix:local`) of an element
+fn qualified(name: &OwnedName) -> String {
+    match name.prefix {
+        Some(ref prefix) => format!("{}:{}", prefix, name.local_name),
+        None => name.local_name.clone(),
+    }
+}
+
+fn write_to_targets(
+    parts: &mut [(Part, EventWriter<Vec<u8>>)],
+    targets: &[Part],
+    ev: &REvent,
+) -> Result<()> {
+    let ev = ev.as_writer_event()
+        .ok_or_else(|| Error::Xml("Failed to build a writer XmlEvent!".to_string()))?;
+    for (_, writer) in parts.iter_mut().filter(|(p, _)| targets.contains(p)) {
+        writer.write(ev.clone())?;
+    }
+    Ok(())
+}
+
+/// Write the root element of a package part, declaring the same namespaces as the flat document.
+fn write_root(
+    writer: &mut EventWriter<Vec<u8>>,
+    root: &str,
+    version: &str,
+    namespace: &Namespace,
+) -> Result<()> {
+    let mut start = WEvent::start_element(root).attr("office:version", version);
+    for (prefix, uri) in namespace {
+        if prefix.is_empty() || prefix == "xml" || prefix == "xmlns" {
+            continue;
+        }
+        start = start.ns(prefix, uri);
+    }
+    writer.write(start)?;
+    Ok(())
+}
+
+/// Read the `office:binary-data` child of a `draw:image`, consuming events up to the end of the image.
+///
+/// Returns the picture (`None` when the image is already a link) and the events of the other
+/// children (e.g. a `text:p` description), to be written back.
+fn read_binary_data(
+    reader: &mut EventReader<&[u8]>,
+    attributes: &[xml::attribute::OwnedAttribute],
+    index: usize,
+) -> Result<(Option<Picture>, Vec<REvent>)> {
+    let media_type = attributes.iter()
+        .find(|a| a.name.local_name == "mime-type")
+        .map(|a| a.value.clone())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    let extension = match media_type.as_str() {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/svg+xml" => "svg",
+        _ => "bin",
+    };
+    let mut in_binary_data = false;
+    let mut found = false;
+    let mut encoded = String::new();
+    let mut children = Vec::new();
+    //Depth of the elements below draw:image
+    let mut depth = 0usize;
+    loop {
+        let ev = reader.next()?;
+        match ev {
+            REvent::StartElement { ref name, .. } if depth == 0 && qualified(name) == "office:binary-data" => {
+                in_binary_data = true;
+                found = true;
+            }
+            REvent::Characters(ref text) | REvent::Whitespace(ref text) if in_binary_data => encoded.push_str(text),
+            REvent::EndElement { ref name } if in_binary_data && qualified(name) == "office:binary-data" => in_binary_data = false,
+            REvent::EndElement { .. } if depth == 0 => break,
+            REvent::EndDocument => return Err(Error::Xml("Document end found!".to_string())),
+            REvent::Whitespace(_) if depth == 0 => {}
+            REvent::StartElement { .. } => {
+                depth += 1;
+                children.push(ev);
+            }
+            REvent::EndElement { .. } => {
+                depth -= 1;
+                children.push(ev);
+            }
+            _ => children.push(ev),
+        }
+    }
+    if !found {
+        return Ok((None, children));
+    }
+    let encoded: String = encoded.split_whitespace().collect();
+    let data = BASE64_STANDARD.decode(encoded).map_err(|e| Error::Xml(e.to_string()))?;
+    let picture = Picture {
+        path: format!("Pictures/image{}.{}", index, extension),
+        media_type,
+        data,
+    };
+    Ok((Some(picture), children))
+}
+
+/// Generate `META-INF/manifest.xml`
+fn manifest(pictures: &[Picture]) -> Result<Vec<u8>> {
+    let mut writer = EmitterConfig::new()
+        .perform_indent(true)
+        .create_writer(Vec::new());
+    writer.write(
+        WEvent::start_element("manifest:manifest")
+            .ns("manifest", "urn:oasis:names:tc:opendocument:xmlns:manifest:1.0")
+            .attr("manifest:version", "1.3")
+    )?;
+    let mut entries = vec![
+        ("/", MIMETYPE),
+        ("content.xml", "text/xml"),
+        ("styles.xml", "text/xml"),
+        ("meta.xml", "text/xml"),
+        ("settings.xml", "text/xml"),
+    ];
+    for picture in pictures {
+        entries.push((picture.path.as_str(), picture.media_type.as_str()));
+    }
+    for (path, media_type) in entries {
+        let mut start = WEvent::start_element("manifest:file-entry")
+            .attr("manifest:full-path", path)
+            .attr("manifest:media-type", media_type);
+        if path == "/" {
+            start = start.attr("manifest:version", "1.3");
+        }
+        writer.write(start)?;
+        writer.write(WEvent::end_element())?;
+    }
+    writer.write(WEvent::end_element())?;
+    Ok(writer.into_inner())
+}
+
+// ===============================================================================================
+// Input
+// ===============================================================================================
+
+/// Top-level elements of a package part, as reader events (including the start and end events)
+struct PartElements {
+    namespace: Namespace,
+    version: Option<String>,
+    elements: Vec<(String, Vec<REvent>)>,
+}
+
+/// Convert an ODT package (e.g. a `.ott` template) into a Flat ODT document.
+pub(crate) fn read_flat(package: &[u8]) -> Result<String> {
+    let mut archive = ZipArchive::new(Cursor::new(package))?;
+    let mut parts = Vec::new();
+    for file_name in ["meta.xml", "settings.xml", "styles.xml", "content.xml"] {
+        let xml = match archive.by_name(file_name) {
+            Ok(mut file) => {
+                let mut xml = Vec::new();
+                file.read_to_end(&mut xml)?;
+                xml
+            }
+            Err(zip::result::ZipError::FileNotFound) if file_name == "meta.xml" || file_name == "settings.xml" => continue,
+            Err(e) => return Err(e.into()),
+        };
+        parts.push(read_part_elements(&xml)?);
+    }
+
+    let mut namespace = Namespace::empty();
+    let mut version = None;
+    for part in &parts {
+        namespace.extend(&part.namespace);
+        version = version.or(part.version.clone());
+    }
+    let mut writer = EmitterConfig::new().create_writer(Vec::new());
+    let mut start = WEvent::start_element("office:document")
+        .attr("office:version", version.as_deref().unwrap_or("1.3"))
+        .attr("office:mimetype", MIMETYPE);
+    for (prefix, uri) in &namespace {
+        if prefix.is_empty() || prefix == "xml" || prefix == "xmlns" {
+            continue;
+        }
+        start = start.ns(prefix, uri);
+    }
+    writer.write(start)?;
+    //Top-level elements in the order of the flat format, merging the ones found in several parts
+    for element in [
+        "office:meta", "office:settings", "office:scripts", "office:font-face-decls", "office:styles",
+        "office:automatic-styles", "office:master-styles", "office:body",
+    ] {
+        let events: Vec<&Vec<REvent>> = parts.iter()
+            .flat_map(|part| part.elements.iter())
+            .filter(|(name, _)| name == element)
+            .map(|(_, events)| events)
+            .collect();
+        if events.is_empty() {
+            continue;
+        }
+        writer.write(WEvent::start_element(element))?;
+        //Automatic styles and font faces are duplicated in `styles.xml` and `content.xml`
+        let mut names = HashSet::new();
+        for element_events in events {
+            let inner = &element_events[1..element_events.len() - 1];
+            for child in split_children(inner) {
+                if let Some(REvent::StartElement { name, attributes, .. }) = child.first() {
+                    let child_name = attributes.iter()
+                        .find(|a| a.name.local_name == "name")
+                        .map(|a| format!("{}#{}", qualified(name), a.value));
+                    if child_name.is_some_and(|n| !names.insert(n)) {
+                        continue;
+                    }
+                }
+                write_with_pictures(&mut writer, &mut archive, child)?;
+            }
+        }
+        writer.write(WEvent::end_element())?;
+    }
+    writer.write(WEvent::end_element())?;
+    String::from_utf8(writer.into_inner()).map_err(|e| Error::Xml(e.to_string()))
+}
+
+/// Read the top-level elements of a package part.
+fn read_part_elements(xml: &[u8]) -> Result<PartElements> {
+    //Comments are kept, as they may be template markers
+    let mut reader = EventReader::new_with_config(xml, ParserConfig::new().ignore_comments(false));
+    let mut part = PartElements {
+        namespace: Namespace::empty(),
+        version: None,
+        elements: Vec::new(),
+    };
+    let mut depth = 0usize;
+    loop {
+        let ev = reader.next()?;
+        match ev {
+            REvent::EndDocument => break,
+            REvent::StartElement { ref attributes, ref namespace, .. } if depth == 0 => {
+                part.namespace = namespace.clone();
+                part.version = attributes.iter()
+                    .find(|a| a.name.local_name == "version")
+                    .map(|a| a.value.clone());
+                depth += 1;
+            }
+            REvent::StartElement { ref name, .. } => {
+                if depth == 1 {
+                    part.elements.push((qualified(name), Vec::new()));
+                }
+                depth += 1;
+                if let Some((_, events)) = part.elements.last_mut() {
+                    events.push(ev);
+                }
+            }
+            REvent::EndElement { .. } => {
+                depth -= 1;
+                if depth > 0 && let Some((_, events)) = part.elements.last_mut() {
+                    events.push(ev);
+                }
+            }
+            other => {
+                if depth > 1 && let Some((_, events)) = part.elements.last_mut() {
+                    events.push(other);
+                }
+            }
+        }
+    }
+    Ok(part)
+}
+
+/// Split the events of an element content into its children (elements or other nodes).
+fn split_children(events: &[REvent]) -> Vec<&[REvent]> {
+    let mut children = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    for (i, ev) in events.iter().enumerate() {
+        match ev {
+            REvent::StartElement { .. } => depth += 1,
+            REvent::EndElement { .. } => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            children.push(&events[start..=i]);
+            start = i + 1;
+        }
+    }
+    children
+}
+
+/// Write events, embedding the pictures of the package referenced by `draw:image` elements.
+fn write_with_pictures(
+    writer: &mut EventWriter<Vec<u8>>,
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    events: &[REvent],
+) -> Result<()> {
+    for ev in events {
+        if let REvent::StartElement { ref name, ref attributes, .. } = *ev
+            && qualified(name) == "draw:image" {
+            let href = attributes.iter()
+                .find(|a| qualified(&a.name) == "xlink:href")
+                .map(|a| a.value.clone());
+            let picture = match href {
+                Some(ref href) if !href.contains("://") => match archive.by_name(href.trim_start_matches("./")) {
+                    Ok(mut file) => {
+                        let mut data = Vec::new();
+                        file.read_to_end(&mut data)?;
+                        Some(data)
+                    }
+                    //A file outside of the package
+                    Err(zip::result::ZipError::FileNotFound) => None,
+                    Err(e) => return Err(e.into()),
+                },
+                _ => None,
+            };
+            if let Some(data) = picture {
+                let mut start = WEvent::start_element("draw:image");
+                for attr in attributes.iter().filter(|a| a.name.prefix.as_deref() != Some("xlink")) {
+                    start = start.attr(attr.name.borrow(), &attr.value);
+                }
+                writer.write(start)?;
+                writer.write(WEvent::start_element("office:binary-data"))?;
+                writer.write(WEvent::characters(&BASE64_STANDARD.encode(data)))?;
+                writer.write(WEvent::end_element())?;
+                continue;
+            }
+        }
+        let ev = ev.as_writer_event()
+            .ok_or_else(|| Error::Xml("Failed to build a writer XmlEvent!".to_string()))?;
+        writer.write(ev)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::prelude::*;
+    use super::*;
+
+    fn unzip(odt: &[u8]) -> ZipArchive<Cursor<&[u8]>> {
+        ZipArchive::new(Cursor::new(odt)).expect("Invalid ZIP archive")
+    }
+
+    fn entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> String {
+        let mut text = String::new();
+        archive.by_name(name).expect(name).read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn mimetype_is_first_stored_entry_and_pictures_are_listed() {
+        let path: PathBuf = std::env::temp_dir().join(format!("odtgen-package-{}.png", std::process::id()));
+        std::fs::write(&path, b"not really a png").unwrap();
+        let mut paragraph = Paragraph::new();
+        paragraph.content.add(Image::new(&path, 1.0, 1.0, ImageAnchor::AsChar));
+        let mut doc = Document::new();
+        doc.body.add(paragraph);
+        let mut odt = Vec::new();
+        let generated = doc.generate_odt(&mut odt);
+        std::fs::remove_file(&path).unwrap();
+        generated.unwrap();
+
+        let mut archive = unzip(&odt);
+        let first = archive.by_index(0).unwrap();
+        assert_eq!(first.name(), "mimetype");
+        assert_eq!(first.compression(), CompressionMethod::Stored);
+        drop(first);
+        assert_eq!(entry(&mut archive, "mimetype"), MIMETYPE);
+
+        let mut picture = Vec::new();
+        archive.by_name("Pictures/image1.png").unwrap().read_to_end(&mut picture).unwrap();
+        assert_eq!(picture, b"not really a png");
+        let manifest = entry(&mut archive, "META-INF/manifest.xml");
+        assert!(manifest.contains(r#"manifest:full-path="Pictures/image1.png""#));
+        assert!(manifest.contains(r#"manifest:media-type="image/png""#));
+        assert!(entry(&mut archive, "content.xml").contains(r#"xlink:href="Pictures/image1.png""#));
+    }
+
+    #[test]
+    fn image_children_other_than_binary_data_are_kept() {
+        let flat = format!(
+            r#"<office:document xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:draw="urn:oasis:names:tc:opendocument:xmlns:drawing:1.0" office:version="1.3"><office:body><office:text><text:p><draw:frame><draw:image draw:mime-type="image/png"><office:binary-data>{}</office:binary-data><text:p>Caption</text:p></draw:image></draw:frame></text:p></office:text></office:body></office:document>"#,
+            BASE64_STANDARD.encode(b"data")
+        );
+        let mut odt = Vec::new();
+        write_odt(flat.as_bytes(), &mut odt).unwrap();
+
+        let mut archive = unzip(&odt);
+        let content = entry(&mut archive, "content.xml");
+        assert!(content.contains("<text:p>Caption</text:p></draw:image>"), "{}", content);
+        assert!(!content.contains("office:binary-data"));
+        let mut picture = Vec::new();
+        archive.by_name("Pictures/image1.png").unwrap().read_to_end(&mut picture).unwrap();
+        assert_eq!(picture, b"data");
+    }
+}
@@ -0,0 +1,157 @@
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use strum_macros::{AsRefStr, EnumString};
+use crate::error::Result;
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+
+/// Page layout (paper size, orientation and margins) referenced by master pages.
+///
+/// Page layouts are always written as automatic styles.
+pub struct PageLayout {
+    name: String,
+    pub width: String,
+    pub height: String,
+    pub orientation: PrintOrientation,
+    pub margin_top: String,
+    pub margin_bottom: String,
+    pub margin_left: String,
+    pub margin_right: String,
+    /// Minimum height of the header area
+    pub header_height: String,
+    /// Minimum height of the footer area
+    pub footer_height: String,
+    /// Page number format (`1`, `i`, `I`, `a`, `A`)
+    pub num_format: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, AsRefStr)]
+#[strum(serialize_all = "kebab-case")]
+pub enum PrintOrientation {
+    Portrait,
+    Landscape,
+}
+
+/// Common paper sizes (portrait dimensions)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, AsRefStr)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum PaperSize {
+    A3,
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PaperSize {
+    /// Width and height of the paper in portrait orientation.
+    pub fn dimensions(&self) -> (&'static str, &'static str) {
+        match self {
+            PaperSize::A3 => ("29.7cm", "42cm"),
+            PaperSize::A4 => ("21.001cm", "29.7cm"),
+            PaperSize::A5 => ("14.801cm", "21.001cm"),
+            PaperSize::Letter => ("21.59cm", "27.94cm"),
+            PaperSize::Legal => ("21.59cm", "35.56cm"),
+        }
+    }
+}
+
+impl PageLayout {
+    /// Create a portrait page layout with 2cm margins for the given paper size.
+    pub fn new<T: Into<String>>(name: T, paper: PaperSize) -> Self {
+        let (width, height) = paper.dimensions();
+        Self {
+            name: name.into(),
+            width: width.to_string(),
+            height: height.to_string(),
+            orientation: PrintOrientation::Portrait,
+            margin_top: "2cm".to_string(),
+            margin_bottom: "2cm".to_string(),
+            margin_left: "2cm".to_string(),
+            margin_right: "2cm".to_string(),
+            header_height: "0cm".to_string(),
+            footer_height: "0cm".to_string(),
+            num_format: "1".to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set the orientation, swapping width and height when needed.
+    pub fn set_orientation(&mut self, orientation: PrintOrientation) {
+        if orientation != self.orientation {
+            std::mem::swap(&mut self.width, &mut self.height);
+            self.orientation = orientation;
+        }
+    }
+
+    /// Helper to switch the layout to landscape.
+    pub fn landscape(mut self) -> Self {
+        self.set_orientation(PrintOrientation::Landscape);
+        self
+    }
+
+    /// Helper to set all margins at once.
+    pub fn with_margins<T: Into<String>>(mut self, margin: T) -> Self {
+        let margin = margin.into();
+        self.margin_top = margin.clone();
+        self.margin_bottom = margin.clone();
+        self.margin_left = margin.clone();
+        self.margin_right = margin;
+        self
+    }
+}
+
+impl FlatOdtXmlWrite for PageLayout {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        writer.write(
+            XmlWriterEvent::start_element("style:page-layout")
+                .attr("style:name", &self.name)
+        )?;
+        writer.write(
+            XmlWriterEvent::start_element("style:page-layout-properties")
+                .attr("fo:page-width", &self.width)
+                .attr("fo:page-height", &self.height)
+                .attr("style:num-format", &self.num_format)
+                .attr("style:print-orientation", self.orientation.as_ref())
+                .attr("fo:margin-top", &self.margin_top)
+                .attr("fo:margin-bottom", &self.margin_bottom)
+                .attr("fo:margin-left", &self.margin_left)
+                .attr("fo:margin-right", &self.margin_right)
+                .attr("style:writing-mode", "lr-tb")
+                .attr("style:footnote-max-height", "0cm")
+        )?;
+        writer.write(
+            XmlWriterEvent::start_element("style:footnote-sep")
+                .attr("style:width", "0.018cm")
+                .attr("style:distance-before-sep", "0.101cm")
+                .attr("style:distance-after-sep", "0.101cm")
+                .attr("style:line-style", "solid")
+                .attr("style:adjustment", "left")
+                .attr("style:rel-width", "25%")
+                .attr("style:color", "#000000")
+        )?;
+        writer.write(XmlWriterEvent::end_element())?; // footnote-sep
+        writer.write(XmlWriterEvent::end_element())?; // page-layout-properties
+        //Header and footer areas
+        for (tag_name, height, spacing_key) in [
+            ("style:header-style", &self.header_height, "fo:margin-bottom"),
+            ("style:footer-style", &self.footer_height, "fo:margin-top"),
+        ] {
+            writer.write(XmlWriterEvent::start_element(tag_name))?;
+            writer.write(
+                XmlWriterEvent::start_element("style:header-footer-properties")
+                    .attr("fo:min-height", height)
+                    .attr(spacing_key, "0.499cm")
+                    .attr("fo:background-color", "transparent")
+                    .attr("draw:fill", "none")
+            )?;
+            writer.write(XmlWriterEvent::end_element())?;
+            writer.write(XmlWriterEvent::end_element())?;
+        }
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+}
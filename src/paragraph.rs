@@ -1,10 +1,13 @@
-use std::borrow::Cow;
 use std::convert::Into;
 use std::io::Write;
 use xml::EventWriter;
 use xml::writer::{XmlEvent as XmlWriterEvent};
 use crate::container::Container;
+use crate::error::Result;
 use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::note::NoteNumbering;
+use crate::statistics::StatisticsWalker;
+use crate::stylesheet::Stylesheet;
 use crate::text::Text;
 
 pub struct Paragraph {
@@ -37,13 +40,37 @@ impl Paragraph {
     }
 }
 
+impl Default for Paragraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FlatOdtXmlWrite for Paragraph {
-    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
         let mut start = XmlWriterEvent::start_element("text:p");
         start = self.add_optional_string_attributes(start);
         writer.write(start)?;
         self.content.write_flat_odt_xml(writer)?;
-        writer.write(XmlWriterEvent::end_element())
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        statistics.add_paragraph();
+        self.content.update_statistics(statistics);
+    }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        self.content.number_notes(numbering);
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
+        self.content.register_automatic_styles(stylesheet);
+    }
+
+    fn is_block(&self) -> bool {
+        true
     }
 
     fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
@@ -0,0 +1,199 @@
+use std::borrow::Cow;
+use std::io::{Read, Write};
+use xml::EventWriter;
+use xml::attribute::{Attribute, OwnedAttribute};
+use xml::name::OwnedName;
+use xml::namespace::Namespace;
+use xml::reader::{EventReader, ParserConfig, XmlEvent as XmlReaderEvent};
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::error::{Error, Result};
+use crate::fodt_parser::{DRAW_NS, TABLE_NS, TEXT_NS};
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::statistics::StatisticsWalker;
+
+/// Elements of the text namespace allowed where paragraphs are (e.g. in table cells)
+const BLOCK_TEXT_ELEMENTS: [&str; 19] = [
+    "p", "h", "list", "numbered-paragraph", "section", "soft-page-break",
+    "table-of-content", "illustration-index", "table-index", "object-index", "user-index",
+    "alphabetical-index", "bibliography",
+    "tracked-changes", "change", "change-start", "change-end",
+    "sequence-decls", "variable-decls",
+];
+
+/// An opaque XML element, written back as it was read.
+///
+/// Used to preserve the elements of parsed documents which have no dedicated type
+/// (e.g. fields, bookmarks or drawings).
+pub struct RawXml {
+    name: OwnedName,
+    attributes: Vec<OwnedAttribute>,
+    /// Namespace declarations needed by the element name and its attributes
+    namespace: Namespace,
+    children: Vec<RawXmlNode>,
+}
+
+/// Child of a raw XML element
+pub enum RawXmlNode {
+    Element(RawXml),
+    Text(String),
+}
+
+impl RawXml {
+    /// Read a whole XML document, returning its root element.
+    ///
+    /// White space used for indentation (containing a line break) is dropped.
+    pub(crate) fn read_document<R: Read>(reader: R) -> Result<RawXml> {
+        let config = ParserConfig::new()
+            .ignore_comments(true)
+            .cdata_to_characters(true)
+            .coalesce_characters(true);
+        let mut reader = EventReader::new_with_config(reader, config);
+        let mut stack: Vec<RawXml> = Vec::new();
+        loop {
+            match reader.next()? {
+                XmlReaderEvent::StartElement { name, attributes, namespace } => {
+                    let namespace = Self::used_namespace(&name, &attributes, &namespace);
+                    stack.push(RawXml { name, attributes, namespace, children: Vec::new() });
+                }
+                XmlReaderEvent::EndElement { .. } => {
+                    let element = stack.pop()
+                        .ok_or_else(|| Error::Xml("Unbalanced end element!".to_string()))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(RawXmlNode::Element(element)),
+                        None => return Ok(element),
+                    }
+                }
+                XmlReaderEvent::Whitespace(text) if text.contains('\n') => {}
+                XmlReaderEvent::Characters(text) | XmlReaderEvent::Whitespace(text) => {
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(RawXmlNode::Text(text));
+                    }
+                }
+                XmlReaderEvent::EndDocument => {
+                    return Err(Error::Xml("Document without root element!".to_string()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Keep only the namespace declarations used by an element and its attributes.
+    fn used_namespace(name: &OwnedName, attributes: &[OwnedAttribute], in_scope: &Namespace) -> Namespace {
+        let mut namespace = Namespace::empty();
+        let prefixes = std::iter::once(name)
+            .chain(attributes.iter().map(|a| &a.name))
+            .filter_map(|n| n.prefix.as_deref());
+        for prefix in prefixes {
+            if let Some(uri) = in_scope.get(prefix) {
+                namespace.put(prefix, uri);
+            }
+        }
+        namespace
+    }
+
+    /// Qualified name of the element (e.g. `text:bookmark`).
+    pub fn name(&self) -> String {
+        self.name.borrow().repr_display().to_string()
+    }
+
+    /// Check the namespace URI and local name of the element.
+    pub fn is(&self, namespace_uri: &str, local_name: &str) -> bool {
+        self.name.local_name == local_name && self.name.namespace.as_deref() == Some(namespace_uri)
+    }
+
+    /// Local name of the element if it belongs to the given namespace.
+    pub fn is_in(&self, namespace_uri: &str) -> Option<&str> {
+        (self.name.namespace.as_deref() == Some(namespace_uri)).then_some(self.name.local_name.as_str())
+    }
+
+    /// Get the value of an attribute by namespace URI and local name.
+    pub fn attribute(&self, namespace_uri: &str, local_name: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|a| a.name.local_name == local_name && a.name.namespace.as_deref() == Some(namespace_uri))
+            .map(|a| a.value.as_str())
+    }
+
+    /// Attributes of the element.
+    pub fn attributes(&self) -> &[OwnedAttribute] {
+        &self.attributes
+    }
+
+    pub fn children(&self) -> &[RawXmlNode] {
+        &self.children
+    }
+
+    /// Consume the element, returning its children.
+    pub fn into_children(self) -> Vec<RawXmlNode> {
+        self.children
+    }
+
+    /// Iterate over the child elements, skipping text.
+    pub fn child_elements(&self) -> impl Iterator<Item = &RawXml> {
+        self.children.iter().filter_map(|c| match c {
+            RawXmlNode::Element(e) => Some(e),
+            RawXmlNode::Text(_) => None,
+        })
+    }
+
+    /// Find the first child element with the given namespace URI and local name.
+    pub fn child(&self, namespace_uri: &str, local_name: &str) -> Option<&RawXml> {
+        self.child_elements().find(|e| e.is(namespace_uri, local_name))
+    }
+
+    /// Concatenated text of the element and its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for child in &self.children {
+            match child {
+                RawXmlNode::Element(e) => text.push_str(&e.text()),
+                RawXmlNode::Text(t) => text.push_str(t),
+            }
+        }
+        text
+    }
+}
+
+impl FlatOdtXmlWrite for RawXml {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let attributes: Vec<Attribute> = self.attributes.iter().map(|a| a.borrow()).collect();
+        writer.write(XmlWriterEvent::StartElement {
+            name: self.name.borrow(),
+            attributes: Cow::Owned(attributes),
+            namespace: Cow::Borrowed(&self.namespace),
+        })?;
+        for child in &self.children {
+            match child {
+                RawXmlNode::Element(e) => e.write_flat_odt_xml(writer)?,
+                RawXmlNode::Text(t) => writer.write(XmlWriterEvent::characters(t))?,
+            }
+        }
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        if self.is(TEXT_NS, "p") || self.is(TEXT_NS, "h") {
+            statistics.add_paragraph();
+        } else if self.is(TABLE_NS, "table") {
+            statistics.add_table();
+        } else if self.is(DRAW_NS, "image") {
+            statistics.add_image();
+        }
+        for child in &self.children {
+            match child {
+                RawXmlNode::Element(e) => e.update_statistics(statistics),
+                //Skip indentation between elements
+                RawXmlNode::Text(t) if t.trim().is_empty() => {}
+                RawXmlNode::Text(t) => statistics.add_text(t),
+            }
+        }
+    }
+
+    fn is_block(&self) -> bool {
+        if let Some(name) = self.is_in(TEXT_NS) {
+            BLOCK_TEXT_ELEMENTS.contains(&name)
+        } else {
+            self.is(TABLE_NS, "table")
+        }
+    }
+}
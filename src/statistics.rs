@@ -0,0 +1,144 @@
+use std::io::Write;
+use xml::EventWriter;
+use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::error::Result;
+use crate::fodt_xml_write::FlatOdtXmlWrite;
+
+/// Document statistics (`meta:document-statistic`), computed from the body of the document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DocumentStatistics {
+    pub table_count: u32,
+    pub image_count: u32,
+    pub paragraph_count: u32,
+    pub word_count: u32,
+    pub character_count: u32,
+    pub non_whitespace_character_count: u32,
+}
+
+/// Computation of the document statistics while walking through the elements
+/// (see `FlatOdtXmlWrite::update_statistics`).
+#[derive(Default)]
+pub struct StatisticsWalker {
+    statistics: DocumentStatistics,
+    /// Whether the last counted character was part of a word, so that a word split
+    /// across several spans is only counted once
+    in_word: bool,
+}
+
+impl StatisticsWalker {
+    /// Count a new paragraph (or heading).
+    pub fn add_paragraph(&mut self) {
+        self.statistics.paragraph_count += 1;
+        self.end_word();
+    }
+
+    /// Count a new table.
+    pub fn add_table(&mut self) {
+        self.statistics.table_count += 1;
+    }
+
+    /// Count a new image.
+    pub fn add_image(&mut self) {
+        self.statistics.image_count += 1;
+    }
+
+    /// Count the words and characters of a text fragment.
+    pub fn add_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.statistics.character_count += 1;
+            if c.is_whitespace() {
+                self.in_word = false;
+            } else {
+                self.statistics.non_whitespace_character_count += 1;
+                if !self.in_word {
+                    self.statistics.word_count += 1;
+                    self.in_word = true;
+                }
+            }
+        }
+    }
+
+    /// Terminate the current word (e.g. at a tab stop).
+    pub fn end_word(&mut self) {
+        self.in_word = false;
+    }
+
+    /// Statistics counted so far
+    pub fn statistics(&self) -> DocumentStatistics {
+        self.statistics
+    }
+}
+
+impl FlatOdtXmlWrite for DocumentStatistics {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let values = [
+            ("meta:table-count", self.table_count),
+            ("meta:image-count", self.image_count),
+            ("meta:paragraph-count", self.paragraph_count),
+            ("meta:word-count", self.word_count),
+            ("meta:character-count", self.character_count),
+            ("meta:non-whitespace-character-count", self.non_whitespace_character_count),
+        ].map(|(key, value)| (key, value.to_string()));
+        let mut start = XmlWriterEvent::start_element("meta:document-statistic");
+        for (key, value) in &values {
+            start = start.attr(*key, value.as_str());
+        }
+        writer.write(start)?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use crate::note::Note;
+    use crate::paragraph::Paragraph;
+    use crate::table::{Table, TableCell, TableColumn, TableRow};
+    use crate::text::Text;
+    use crate::text_span::TextSpan;
+
+    #[test]
+    fn words_split_across_spans_are_counted_once() {
+        let mut walker = StatisticsWalker::default();
+        walker.add_paragraph();
+        walker.add_text("Hello wor");
+        walker.add_text("ld, again");
+        walker.add_paragraph();
+        walker.add_text("next");
+        let statistics = walker.statistics();
+        assert_eq!((statistics.paragraph_count, statistics.word_count), (2, 4));
+        assert_eq!((statistics.character_count, statistics.non_whitespace_character_count), (22, 20));
+    }
+
+    #[test]
+    fn notes_and_tables_are_counted() {
+        let mut doc = Document::new();
+        let mut paragraph = Paragraph::from_text("Hello wor");
+        paragraph.content.add(TextSpan::from_text("ld, again"));
+        paragraph.content.add(Note::footnote("Note text"));
+        paragraph.content.add(Text { text: " end".to_string() });
+        doc.body.add(paragraph);
+        let mut table = Table::new("T");
+        table.add_column(TableColumn::new());
+        table.add_column(TableColumn::new());
+        let mut row = TableRow::new();
+        for text in ["a b", "c"] {
+            let mut cell = TableCell::new();
+            cell.content.add(Paragraph::from_text(text));
+            row.add_cell(cell);
+        }
+        table.add_row(row);
+        doc.body.add(table);
+
+        assert_eq!(doc.statistics(), DocumentStatistics {
+            table_count: 1,
+            image_count: 0,
+            paragraph_count: 4,
+            word_count: 9,
+            character_count: 35,
+            non_whitespace_character_count: 30,
+        });
+    }
+}
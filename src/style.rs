@@ -1,11 +1,14 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::io::Write;
 use xml::EventWriter;
 use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::error::Result;
 use crate::fodt_xml_write::FlatOdtXmlWrite;
 use strum_macros::{EnumString, AsRefStr, EnumIter};
 
 /// A style to be used in a Stylesheet
+///
+/// Property groups and their attributes are written in a stable (sorted) order.
 pub struct Style {
     name: String,
     pub family: StyleFamily,
@@ -14,8 +17,10 @@ pub struct Style {
     pub next_style_name: Option<String>,
     pub display_name: Option<String>,
     pub class: Option<String>,
+    /// Master page started by paragraphs with this style (paragraph family only)
+    pub master_page_name: Option<String>,
     pub default_outline_level: Option<u8>,
-    pub properties: HashMap<StylePropertyGroup, StyleItem>,
+    pub properties: BTreeMap<StylePropertyGroup, StyleItem>,
 }
 
 impl Style {
@@ -27,9 +32,10 @@ impl Style {
             next_style_name: None,
             display_name: None,
             default_outline_level: None,
-            properties: HashMap::new(),
+            properties: BTreeMap::new(),
             automatic: false,
-            class: None
+            class: None,
+            master_page_name: None,
         }
     }
     pub fn name(&self) -> &str {
@@ -38,7 +44,7 @@ impl Style {
 }
 
 impl FlatOdtXmlWrite for Style {
-    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
         let attr_value: String;
         //Top-level element
         let mut start = XmlWriterEvent::start_element("style:style")
@@ -52,7 +58,7 @@ impl FlatOdtXmlWrite for Style {
         }
         writer.write(start)?;
         //Properties
-        for (group, style_item) in  self.properties.iter() {
+        for (group, style_item) in self.properties.iter() {
             let tag_name = format!("style:{}", group.as_ref());
             let mut start = XmlWriterEvent::start_element(tag_name.as_str());
             for (key, value)  in &style_item.simple_attributes {
@@ -78,7 +84,8 @@ impl FlatOdtXmlWrite for Style {
             //Inner children
             writer.write(XmlWriterEvent::end_element())?;
         }
-        writer.write(XmlWriterEvent::end_element())
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
     }
 
     fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
@@ -87,6 +94,7 @@ impl FlatOdtXmlWrite for Style {
             ("style:display-name", self.display_name.as_deref()),
             ("style:next-style-name", self.next_style_name.as_deref()),
             ("style:class", self.class.as_deref()),
+            ("style:master-page-name", self.master_page_name.as_deref()),
         ]
     }
 }
@@ -97,7 +105,7 @@ impl FlatOdtXmlWrite for Style {
 
 #[derive(Default)]
 pub struct StyleItem {
-    simple_attributes: HashMap<String, String>,
+    simple_attributes: BTreeMap<String, String>,
     tab_stops: Vec<TabStopSpec>
 }
 
@@ -136,7 +144,7 @@ pub enum StyleFamily {
 // Property Groups
 // ======================================================================================
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, EnumIter, AsRefStr)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumString, EnumIter, AsRefStr)]
 #[strum(serialize_all = "kebab-case")]
 pub enum StylePropertyGroup {
     ParagraphProperties,
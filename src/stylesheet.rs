@@ -1,49 +1,168 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
 use std::io::Write;
 use xml::EventWriter;
+use crate::error::Result;
+use crate::fodt_parser::STYLE_NS;
 use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::list_style::ListStyle;
+use crate::outline_style::OutlineStyle;
+use crate::page_layout::PageLayout;
+use crate::raw_xml::RawXml;
 use crate::style::Style;
 use crate::stylesheet_parser::StylesheetParser;
 
 const WRITER_STYLES_YAML: &str = include_str!("../assets/writer_default_stylesheet.yaml");
 
+/// Styles of a document, written sorted by name (so the output is reproducible)
 pub struct Stylesheet {
-    styles: HashMap<String, Style>,
+    styles: BTreeMap<String, Style>,
+    page_layouts: BTreeMap<String, PageLayout>,
+    list_styles: BTreeMap<String, ListStyle>,
+    outline_style: Option<OutlineStyle>,
+    /// Unsupported style elements of parsed documents, written back unchanged
+    raw_styles: Vec<RawXml>,
+    raw_automatic_styles: Vec<RawXml>,
 }
 
 impl Stylesheet {
     pub fn new() -> Self {
         Stylesheet {
-            styles: HashMap::new(),
+            styles: BTreeMap::new(),
+            page_layouts: BTreeMap::new(),
+            list_styles: BTreeMap::new(),
+            outline_style: None,
+            raw_styles: Vec::new(),
+            raw_automatic_styles: Vec::new(),
         }
     }
 
-    pub(crate) fn write_styles(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
-        for (_key, style) in &self.styles {
+    pub(crate) fn write_styles(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        for style in self.styles.values() {
             if style.automatic {
                 continue;
             }
             style.write_flat_odt_xml(writer)?;
         }
+        for list_style in self.list_styles.values() {
+            if !list_style.automatic {
+                list_style.write_flat_odt_xml(writer)?;
+            }
+        }
+        if let Some(ref outline_style) = self.outline_style {
+            outline_style.write_flat_odt_xml(writer)?;
+        }
+        for raw_style in &self.raw_styles {
+            raw_style.write_flat_odt_xml(writer)?;
+        }
         Ok(())
     }
 
-    pub(crate) fn write_automatic_styles(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
-        for (_key, style) in &self.styles {
+    pub(crate) fn write_automatic_styles(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        for style in self.styles.values() {
             if !style.automatic {
                 continue;
             }
             style.write_flat_odt_xml(writer)?;
         }
+        for list_style in self.list_styles.values() {
+            if list_style.automatic {
+                list_style.write_flat_odt_xml(writer)?;
+            }
+        }
+        for page_layout in self.page_layouts.values() {
+            page_layout.write_flat_odt_xml(writer)?;
+        }
+        for raw_style in &self.raw_automatic_styles {
+            raw_style.write_flat_odt_xml(writer)?;
+        }
         Ok(())
     }
 
-    /// Add a style to the stylesheet. A previous one with the same will be overridden.
+    /// Keys (see `style_key`) of all the elements written by the stylesheet.
+    pub(crate) fn style_keys(&self) -> HashSet<String> {
+        let mut keys = HashSet::new();
+        keys.extend(self.styles.keys().map(|name| style_key("style:style", Some(name), None)));
+        keys.extend(self.list_styles.keys().map(|name| style_key("text:list-style", Some(name), None)));
+        keys.extend(self.page_layouts.keys().map(|name| style_key("style:page-layout", Some(name), None)));
+        if self.outline_style.is_some() {
+            //A document has a single outline style, whatever its name
+            keys.insert(style_key("text:outline-style", None, None));
+        }
+        for raw_style in self.raw_styles.iter().chain(&self.raw_automatic_styles) {
+            keys.insert(style_key(
+                &raw_style.name(),
+                raw_style.attribute(STYLE_NS, "name"),
+                raw_style.attribute(STYLE_NS, "family"),
+            ));
+        }
+        keys
+    }
+
+    /// Add a style to the stylesheet, replacing any style with the same name.
     pub fn add_style(&mut self, style: Style) {
         let name = style.name();
         self.styles.insert(name.to_string(), style);
     }
 
+    /// Add an automatic style generated for an element (e.g. the width of a table), unless a
+    /// style which is not automatic already has its name.
+    pub(crate) fn add_automatic_style(&mut self, style: Style) {
+        if self.styles.get(style.name()).is_none_or(|s| s.automatic) {
+            self.add_style(style);
+        }
+    }
+
+    /// Styles of the stylesheet, sorted by name.
+    pub fn styles(&self) -> impl Iterator<Item = &Style> {
+        self.styles.values()
+    }
+
+    /// Check whether a style with the given name exists.
+    pub fn contains(&self, name: &str) -> bool {
+        self.styles.contains_key(name)
+    }
+
+    /// Add a page layout to the stylesheet, replacing any page layout with the same name.
+    pub fn add_page_layout(&mut self, page_layout: PageLayout) {
+        let name = page_layout.name();
+        self.page_layouts.insert(name.to_string(), page_layout);
+    }
+
+    /// Get a page layout by name.
+    pub fn page_layout(&self, name: &str) -> Option<&PageLayout> {
+        self.page_layouts.get(name)
+    }
+
+    /// Get a page layout by name for modification.
+    pub fn page_layout_mut(&mut self, name: &str) -> Option<&mut PageLayout> {
+        self.page_layouts.get_mut(name)
+    }
+
+    /// Add a list style to the stylesheet, replacing any list style with the same name.
+    pub fn add_list_style(&mut self, list_style: ListStyle) {
+        let name = list_style.name();
+        self.list_styles.insert(name.to_string(), list_style);
+    }
+
+    /// Add a style element without a dedicated type (e.g. graphic styles of parsed documents).
+    pub fn add_raw_style(&mut self, raw_style: RawXml, automatic: bool) {
+        if automatic {
+            self.raw_automatic_styles.push(raw_style);
+        } else {
+            self.raw_styles.push(raw_style);
+        }
+    }
+
+    /// Set the outline style, controlling the numbering of headings.
+    pub fn set_outline_style(&mut self, outline_style: OutlineStyle) {
+        self.outline_style = Some(outline_style);
+    }
+
+    /// Get the outline style for modification.
+    pub fn outline_style_mut(&mut self) -> Option<&mut OutlineStyle> {
+        self.outline_style.as_mut()
+    }
+
     /// Append another stylesheet to this one, overwriting styles with the same name.
     ///
     /// Documents are created with a default stylesheet. This method is the recommended way
@@ -51,12 +170,30 @@ impl Stylesheet {
     #[warn(unused)]
     pub fn extend(&mut self, other: Stylesheet) {
         self.styles.extend(other.styles);
+        self.page_layouts.extend(other.page_layouts);
+        self.list_styles.extend(other.list_styles);
+        self.raw_styles.extend(other.raw_styles);
+        self.raw_automatic_styles.extend(other.raw_automatic_styles);
+        if other.outline_style.is_some() {
+            self.outline_style = other.outline_style;
+        }
     }
 }
 
+/// Identify a style-like element by tag name and style name (or family, for default styles).
+///
+/// Elements of the template with the same key as an element of the stylesheet are replaced.
+pub(crate) fn style_key(tag_name: &str, name: Option<&str>, family: Option<&str>) -> String {
+    format!("{}#{}", tag_name, name.or(family).unwrap_or_default())
+}
+
 impl Default for Stylesheet {
     fn default() -> Self {
-        let stylesheet = StylesheetParser::parse_yaml(WRITER_STYLES_YAML);
-        stylesheet.expect("Fail to read default stylesheet!")
+        let mut stylesheet = StylesheetParser::parse_yaml(WRITER_STYLES_YAML)
+            .expect("Fail to read default stylesheet!");
+        if stylesheet.outline_style.is_none() {
+            stylesheet.outline_style = Some(OutlineStyle::new());
+        }
+        stylesheet
     }
 }
\ No newline at end of file
@@ -18,11 +18,40 @@
 //!   text-properties:
 //!     fo:font-family: "Liberation Sans"
 //!     fo:font-weight: bold
+//!
+//! - name: Landscape
+//!   family: page-layout
+//!   paper: a4
+//!   orientation: landscape
+//!   margin-left: 2.5cm
+//!
+//! - name: CorporateBullets
+//!   family: list
+//!   levels:
+//!     - level: 1
+//!       bullet-char: "▪"
+//!     - level: 2
+//!       num-format: "1"
+//!       num-suffix: ")"
+//!       margin-left: 1.5cm
+//!
+//! - name: Outline
+//!   family: outline
+//!   levels:
+//!     - level: 1
+//!       num-format: "1"
+//!     - level: 2
+//!       num-format: "1"
+//!       display-levels: 2
 //! ```
 
 use serde_yaml_ng::Sequence;
 use serde_yaml_ng::Value;
 use strum::IntoEnumIterator;
+use crate::error::{Error, Result};
+use crate::list_style::{ListLabel, ListLevel, ListStyle, LIST_LEVELS};
+use crate::outline_style::OutlineStyle;
+use crate::page_layout::{PageLayout, PaperSize, PrintOrientation};
 use crate::stylesheet::Stylesheet;
 use crate::style::{Style, StyleFamily, StyleItem, StylePropertyGroup, TabStopSpec};
 
@@ -31,31 +60,47 @@ pub struct StylesheetParser {
 }
 
 impl StylesheetParser {
-    pub fn parse_yaml(yaml: &str) -> Result<Stylesheet, String> {
-        let styles_seq: Sequence = serde_yaml_ng::from_str(&yaml).map_err(|e| e.to_string())?;
+    pub fn parse_yaml(yaml: &str) -> Result<Stylesheet> {
+        let styles_seq: Sequence = serde_yaml_ng::from_str(yaml)
+            .map_err(|e| Error::stylesheet("", "", e.to_string()))?;
         let mut stylesheet = Stylesheet::new();
         for style_dict in styles_seq {
             let style_dict = style_dict.as_mapping()
-                .expect("All styles should be mappings!");
+                .ok_or_else(|| Error::stylesheet("", "", "All styles should be mappings!"))?;
             //Base
-            let name = Self::yaml_required_string(style_dict, "name");
-            let family =  Self::yaml_required_string(style_dict, "family");
-            let family = family.as_str().parse::<StyleFamily>().map_err(|e| e.to_string())?;
+            let name = Self::yaml_required_string(style_dict, "", "name")?;
+            let family =  Self::yaml_required_string(style_dict, &name, "family")?;
+            match family.as_str() {
+                "page-layout" => {
+                    stylesheet.add_page_layout(Self::parse_page_layout(style_dict, name)?);
+                    continue;
+                }
+                "list" => {
+                    stylesheet.add_list_style(Self::parse_list_style(style_dict, name)?);
+                    continue;
+                }
+                "outline" => {
+                    stylesheet.set_outline_style(Self::parse_outline_style(style_dict, name)?);
+                    continue;
+                }
+                _ => {}
+            }
+            let family = family.as_str().parse::<StyleFamily>()
+                .map_err(|e| Error::stylesheet(&name, "family", e.to_string()))?;
             let mut style = Style::new(name, family);
             //Normal style or Automatic style
-            if let Some(scope) = Self::yaml_optional_string(style_dict, "_scope") {
-                if scope == "automatic" {
-                    style.automatic = true;
-                }
+            if let Some(scope) = Self::yaml_optional_string(style_dict, "_scope")
+                && scope == "automatic" {
+                style.automatic = true;
             }
             //Parent style name, next style name, class...
             style.parent_style_name = Self::yaml_optional_string(style_dict, "parent-style-name");
             style.next_style_name = Self::yaml_optional_string(style_dict, "next-style-name");
             style.class = Self::yaml_optional_string(style_dict, "class");
             style.display_name = Self::yaml_optional_string(style_dict, "display-name");
+            style.master_page_name = Self::yaml_optional_string(style_dict, "master-page-name");
             style.default_outline_level = Self::yaml_optional_u64(style_dict, "default-outline-level")
-                .map(|v| v.try_into().ok())
-                .flatten();
+                .and_then(|v| v.try_into().ok());
             //Properties
             Self::parse_properties(style_dict, &mut style)?;
             stylesheet.add_style(style);
@@ -63,36 +108,34 @@ impl StylesheetParser {
         Ok(stylesheet)
     }
 
-    fn parse_properties(mapping: &serde_yaml_ng::Mapping, style: &mut Style) -> Result<(), String> {
+    fn parse_properties(mapping: &serde_yaml_ng::Mapping, style: &mut Style) -> Result<()> {
         for group in StylePropertyGroup::iter() {
             let group_key = group.as_ref();
             //If the current property exists in the dictionary
             if let Some(property_val) = mapping.get(group_key) {
-                if !property_val.is_mapping() {
-                    return Err(format!("Property {} should be a dictionary!", group_key));
-                }
-                let property_dict = property_val.as_mapping().unwrap();
+                let property_dict = property_val.as_mapping()
+                    .ok_or_else(|| Error::stylesheet(style.name(), group_key, "Property group should be a dictionary!"))?;
                 let mut style_item = StyleItem::default();
                 for (k, v) in property_dict {
-                    let error = format!("Invalid key: {:?}!", k);
-                    let k = k.as_str().unwrap_or(error.as_str());
+                    let k = k.as_str()
+                        .ok_or_else(|| Error::stylesheet(style.name(), format!("{:?}", k), "Invalid key!"))?;
                     match v {
                         Value::String(s) => { style_item.set(k, s)},
                         Value::Number(n) => { style_item.set(k, n.to_string().as_str())},
                         Value::Sequence(seq) => {
                             if k == "tab-stops" {
                                 if group == StylePropertyGroup::ParagraphProperties {
-                                    Self::parse_tab_stops(seq, &mut style_item)?;
+                                    Self::parse_tab_stops(style.name(), seq, &mut style_item)?;
                                 }
                                 else {
-                                    return Err("Tab stops only allowed in paragraph properties!".to_string());
+                                    return Err(Error::stylesheet(style.name(), k, "Tab stops only allowed in paragraph properties!"));
                                 }
                             }
                         }
                         Value::Bool(b) => {
-                            style_item.set(k, &b.to_string().as_str())
+                            style_item.set(k, b.to_string().as_str())
                         }
-                        _ => return Err(format!("Invalid value for: '{}'", k)),
+                        _ => return Err(Error::stylesheet(style.name(), k, "Invalid value!")),
                     };
                 }
                 style.properties.insert(group, style_item);
@@ -101,11 +144,130 @@ impl StylesheetParser {
         Ok(())
     }
 
-    fn parse_tab_stops(tab_stops_seq: &serde_yaml_ng::Sequence, style_item: &mut StyleItem) -> Result<(), String> {
+    fn parse_page_layout(mapping: &serde_yaml_ng::Mapping, name: String) -> Result<PageLayout> {
+        let paper = match Self::yaml_optional_string(mapping, "paper") {
+            Some(paper) => paper.parse::<PaperSize>()
+                .map_err(|e| Error::stylesheet(&name, "paper", e.to_string()))?,
+            None => PaperSize::A4,
+        };
+        let mut page_layout = PageLayout::new(name.as_str(), paper);
+        if let Some(orientation) = Self::yaml_optional_string(mapping, "orientation") {
+            let orientation = orientation.parse::<PrintOrientation>()
+                .map_err(|e| Error::stylesheet(&name, "orientation", e.to_string()))?;
+            page_layout.set_orientation(orientation);
+        }
+        let fields = [
+            ("width", &mut page_layout.width),
+            ("height", &mut page_layout.height),
+            ("margin-top", &mut page_layout.margin_top),
+            ("margin-bottom", &mut page_layout.margin_bottom),
+            ("margin-left", &mut page_layout.margin_left),
+            ("margin-right", &mut page_layout.margin_right),
+            ("header-height", &mut page_layout.header_height),
+            ("footer-height", &mut page_layout.footer_height),
+            ("num-format", &mut page_layout.num_format),
+        ];
+        for (key, field) in fields {
+            if let Some(value) = Self::yaml_optional_scalar(mapping, &name, key)? {
+                *field = value;
+            }
+        }
+        Ok(page_layout)
+    }
+
+    fn parse_list_style(mapping: &serde_yaml_ng::Mapping, name: String) -> Result<ListStyle> {
+        let mut list_style = ListStyle::new(name.as_str());
+        list_style.display_name = Self::yaml_optional_string(mapping, "display-name");
+        list_style.automatic = Self::yaml_optional_string(mapping, "_scope")
+            .is_some_and(|scope| scope == "automatic");
+        let levels = match mapping.get("levels") {
+            Some(Value::Sequence(levels)) => levels,
+            Some(_) => return Err(Error::stylesheet(&name, "levels", "Levels must be a list!")),
+            None => return Ok(list_style),
+        };
+        for level in levels {
+            let level = level.as_mapping()
+                .ok_or_else(|| Error::stylesheet(&name, "levels", "Levels must be mappings!"))?;
+            list_style.set_level(Self::parse_list_level(level, &name)?);
+        }
+        Ok(list_style)
+    }
+
+    fn parse_outline_style(mapping: &serde_yaml_ng::Mapping, name: String) -> Result<OutlineStyle> {
+        let mut outline_style = OutlineStyle::new();
+        let levels = match mapping.get("levels") {
+            Some(Value::Sequence(levels)) => levels,
+            Some(_) => return Err(Error::stylesheet(&name, "levels", "Levels must be a list!")),
+            None => return Ok(outline_style),
+        };
+        for level_dict in levels {
+            let level_dict = level_dict.as_mapping()
+                .ok_or_else(|| Error::stylesheet(&name, "levels", "Levels must be mappings!"))?;
+            let mut level = Self::parse_list_level(level_dict, &name)?;
+            if let ListLabel::Bullet(_) = level.label {
+                return Err(Error::stylesheet(&name, "bullet-char", "Outline levels cannot use bullets!"));
+            }
+            //Outline levels are not indented unless explicitly requested
+            for (key, field) in [
+                ("margin-left", &mut level.margin_left),
+                ("text-indent", &mut level.text_indent),
+                ("tab-stop-position", &mut level.tab_stop_position),
+            ] {
+                if !level_dict.contains_key(key) {
+                    *field = None;
+                }
+            }
+            outline_style.set_level(level);
+        }
+        Ok(outline_style)
+    }
+
+    /// Parse a level of a list or outline style.
+    fn parse_list_level(mapping: &serde_yaml_ng::Mapping, style_name: &str) -> Result<ListLevel> {
+        let level = Self::yaml_optional_u64(mapping, "level")
+            .filter(|level| (1..=LIST_LEVELS as u64).contains(level))
+            .ok_or_else(|| Error::stylesheet(style_name, "level", "Level must be a number from 1 to 10!"))?
+            as u8;
+        let bullet_char = Self::yaml_optional_scalar(mapping, style_name, "bullet-char")?;
+        let num_format = Self::yaml_optional_scalar(mapping, style_name, "num-format")?;
+        let mut list_level = match (bullet_char, num_format) {
+            (Some(bullet_char), None) => ListLevel::bullet(level, &bullet_char),
+            (None, Some(num_format)) => {
+                let mut list_level = ListLevel::number(level, &num_format);
+                list_level.label = ListLabel::Number {
+                    num_format,
+                    display_levels: Self::yaml_optional_u64(mapping, "display-levels")
+                        .and_then(|v| v.try_into().ok()),
+                    start_value: Self::yaml_optional_u64(mapping, "start-value")
+                        .and_then(|v| v.try_into().ok()),
+                };
+                list_level
+            }
+            _ => return Err(Error::stylesheet(style_name, "num-format",
+                "Each level needs either a bullet-char or a num-format!")),
+        };
+        let fields = [
+            ("num-prefix", &mut list_level.num_prefix),
+            ("num-suffix", &mut list_level.num_suffix),
+            ("text-style-name", &mut list_level.text_style_name),
+            ("margin-left", &mut list_level.margin_left),
+            ("text-indent", &mut list_level.text_indent),
+            ("tab-stop-position", &mut list_level.tab_stop_position),
+            ("label-followed-by", &mut list_level.label_followed_by),
+        ];
+        for (key, field) in fields {
+            if let Some(value) = Self::yaml_optional_scalar(mapping, style_name, key)? {
+                *field = Some(value);
+            }
+        }
+        Ok(list_level)
+    }
+
+    fn parse_tab_stops(style_name: &str, tab_stops_seq: &serde_yaml_ng::Sequence, style_item: &mut StyleItem) -> Result<()> {
         for tab_stop in tab_stops_seq {
             match tab_stop {
                 Value::Mapping(mapping) => {
-                    let position = Self::yaml_required_string(mapping, "position");
+                    let position = Self::yaml_required_string(mapping, style_name, "position")?;
                     let type_ = Self::yaml_optional_string(mapping, "type");
                     let tab_stop = TabStopSpec {
                         position,
@@ -113,7 +275,7 @@ impl StylesheetParser {
                     };
                     style_item.add_tab_stop(tab_stop);
                 }
-                _ => return Err("Tab stops must be mappings!".to_string()),
+                _ => return Err(Error::stylesheet(style_name, "tab-stops", "Tab stops must be mappings!")),
             }
         }
         Ok(())
@@ -125,16 +287,95 @@ impl StylesheetParser {
             .map(str::to_string)
     }
 
+    /// Optional string or number, converted to string
+    fn yaml_optional_scalar(mapping: &serde_yaml_ng::Mapping, style_name: &str, key: &str) -> Result<Option<String>> {
+        match mapping.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(Value::Number(n)) => Ok(Some(n.to_string())),
+            Some(_) => Err(Error::stylesheet(style_name, key, "Invalid value!")),
+        }
+    }
+
     fn yaml_optional_u64(mapping: &serde_yaml_ng::Mapping, key: &str) -> Option<u64> {
         mapping.get(key)
             .and_then(|v| v.as_u64())
     }
 
-    fn yaml_required_string(mapping: &serde_yaml_ng::Mapping, key: &str) -> String {
+    fn yaml_required_string(mapping: &serde_yaml_ng::Mapping, style_name: &str, key: &str) -> Result<String> {
         mapping.get(key)
             .and_then(|v| v.as_str())
-            .expect("Required field invalid or not found!")
-            .to_string()
+            .map(str::to_string)
+            .ok_or_else(|| Error::stylesheet(style_name, key, "Required field invalid or not found!"))
     }
 
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(yaml: &str) -> serde_yaml_ng::Mapping {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    fn error_key(yaml: &str) -> String {
+        match StylesheetParser::parse_yaml(yaml) {
+            Err(Error::Stylesheet { key, .. }) => key,
+            other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn list_levels_are_parsed() {
+        let style = StylesheetParser::parse_list_style(&mapping("
+levels:
+  - level: 2
+    num-format: \"1\"
+    num-suffix: \")\"
+    display-levels: 2
+    margin-left: 1.5cm
+  - level: 1
+    bullet-char: \"▪\"
+"), "L".to_string()).unwrap();
+        assert_eq!(style.levels.len(), 2);
+        assert!(matches!(style.levels[0].label, ListLabel::Bullet(ref c) if c == "▪"));
+        let number = &style.levels[1];
+        assert_eq!(number.level, 2);
+        assert!(matches!(number.label, ListLabel::Number { ref num_format, display_levels: Some(2), .. } if num_format == "1"));
+        assert_eq!(number.num_suffix.as_deref(), Some(")"));
+        assert_eq!(number.margin_left.as_deref(), Some("1.5cm"));
+    }
+
+    #[test]
+    fn invalid_list_levels_are_rejected() {
+        for level in ["0", "11", "first"] {
+            let yaml = format!("- name: L\n  family: list\n  levels:\n    - level: {}\n      bullet-char: \"-\"\n", level);
+            assert_eq!(error_key(&yaml), "level", "level {}", level);
+        }
+        let yaml = "- name: L\n  family: list\n  levels:\n    - level: 10\n      bullet-char: \"-\"\n";
+        assert!(StylesheetParser::parse_yaml(yaml).is_ok());
+        assert_eq!(error_key("- name: L\n  family: list\n  levels:\n    - level: 1\n"), "num-format");
+        assert_eq!(error_key("- name: L\n  family: list\n  levels: 1\n"), "levels");
+    }
+
+    #[test]
+    fn outline_levels_are_not_indented_by_default() {
+        let mut stylesheet = StylesheetParser::parse_yaml("
+- name: Outline
+  family: outline
+  levels:
+    - level: 1
+      num-format: \"I\"
+    - level: 2
+      num-format: \"1\"
+      margin-left: 1cm
+").unwrap();
+        let outline = stylesheet.outline_style_mut().unwrap();
+        let level = |level: u8| outline.levels.iter().find(|l| l.level == level).unwrap();
+        assert!(matches!(level(1).label, ListLabel::Number { ref num_format, .. } if num_format == "I"));
+        assert_eq!(level(1).margin_left, None);
+        assert_eq!(level(2).margin_left.as_deref(), Some("1cm"));
+        assert_eq!(level(2).text_indent, None);
+        assert_eq!(error_key("- name: Outline\n  family: outline\n  levels:\n    - level: 1\n      bullet-char: \"-\"\n"), "bullet-char");
+    }
+}
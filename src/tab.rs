@@ -1,7 +1,9 @@
 use std::io::Write;
 use xml::EventWriter;
 use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::error::Result;
 use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::statistics::StatisticsWalker;
 
 pub struct TabStop {}
 
@@ -12,10 +14,21 @@ impl TabStop {
     }
 }
 
+impl Default for TabStop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FlatOdtXmlWrite for TabStop {
-    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
         let start = XmlWriterEvent::start_element("text:tab");
         writer.write(start)?;
-        writer.write(XmlWriterEvent::end_element())
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        statistics.add_text("\t");
     }
 }
@@ -1,14 +1,33 @@
 use std::io::Write;
+use std::time::Duration;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use xml::EventWriter;
 use crate::container::Container;
+use crate::error::{Error, Result};
 use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::metadata::format_duration;
+use crate::note::NoteNumbering;
+use crate::paragraph::Paragraph;
+use crate::statistics::StatisticsWalker;
+use crate::style::{Style, StyleFamily, StyleItem, StylePropertyGroup};
+use crate::stylesheet::Stylesheet;
+use crate::table_preset::{RowKind, TablePreset};
+use strum_macros::{AsRefStr, EnumString};
 use xml::writer::{XmlEvent as XmlWriterEvent};
 
 pub struct Table {
     pub name: String,
     pub style_name: Option<String>,
+    /// Total width (e.g. `17cm`), written in an automatic table style
+    pub width: Option<String>,
+    /// Horizontal alignment, written in an automatic table style
+    pub alignment: Option<TableAlignment>,
+    /// Borders and shading of the rows and cells which have no style of their own
+    pub preset: Option<TablePreset>,
 
     pub columns: Vec<TableColumn>,
+    /// Rows repeated at the top of each page (`table:table-header-rows`)
+    pub header_rows: Vec<TableRow>,
     pub rows: Vec<TableRow>,
 }
 
@@ -17,7 +36,11 @@ impl Table {
         Self {
             name: name.to_string(),
             style_name: None,
+            width: None,
+            alignment: None,
+            preset: None,
             columns: Vec::new(),
+            header_rows: Vec::new(),
             rows: Vec::new()
         }
     }
@@ -29,31 +52,175 @@ impl Table {
     pub fn add_row(&mut self, row: TableRow) {
         self.rows.push(row);
     }
-}
 
-impl FlatOdtXmlWrite for Table {
-    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
+    /// Add a row repeated at the top of each page when the table spans several pages.
+    pub fn add_header_row(&mut self, row: TableRow) {
+        self.header_rows.push(row);
+    }
+
+    /// Number of columns declared by the column specs
+    pub fn column_count(&self) -> usize {
+        self.columns.iter().map(|c| c.number_columns_repeated.unwrap_or(1) as usize).sum()
+    }
+
+    /// Automatic style holding the width, alignment and preset properties of the table
+    /// (based on `style_name`)
+    fn automatic_style(&self) -> Option<Style> {
+        if self.width.is_none() && self.alignment.is_none() && self.preset.is_none() {
+            return None;
+        }
+        let name = automatic_style_name("Table", &[
+            self.style_name.as_deref(),
+            self.preset.as_ref().map(AsRef::as_ref),
+            self.width.as_deref(),
+            self.alignment.as_ref().map(AsRef::as_ref),
+        ]);
+        let mut properties = StyleItem::default();
+        if let Some(ref preset) = self.preset {
+            preset.set_table_properties(&mut properties);
+        }
+        if let Some(ref width) = self.width {
+            properties.set("style:width", width);
+        }
+        if let Some(ref alignment) = self.alignment {
+            properties.set("table:align", alignment.as_ref());
+        }
+        Some(automatic_style(name, StyleFamily::Table, self.style_name.as_deref(), StylePropertyGroup::TableProperties, properties))
+    }
+
+    /// Write the start of the table, its column specs and header rows, returning the layout
+    /// used to write the other rows.
+    pub(crate) fn write_start(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<TableLayout> {
+        let style_name = self.automatic_style().map(|s| s.name().to_string()).or_else(|| self.style_name.clone());
         let mut start = XmlWriterEvent::start_element("table:table")
             .attr("table:name", &self.name);
-        start = self.add_optional_string_attributes(start);
+        if let Some(ref style_name) = style_name {
+            start = start.attr("table:style-name", style_name);
+        }
         writer.write(start)?;
         //Column specs
         for column in &self.columns {
             column.write_flat_odt_xml(writer)?;
         }
+        let mut layout = TableLayout::new(self);
+        if !self.header_rows.is_empty() {
+            writer.write(XmlWriterEvent::start_element("table:table-header-rows"))?;
+            for row in &self.header_rows {
+                layout.write_header_row(row, writer)?;
+            }
+            layout.check_spans_ended()?;
+            writer.write(XmlWriterEvent::end_element())?;
+        }
+        Ok(layout)
+    }
+}
+
+impl FlatOdtXmlWrite for Table {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let mut layout = self.write_start(writer)?;
         //Rows and data
-        for row in &self.rows {
-            row.write_flat_odt_xml(writer)?;
+        for (i, row) in self.rows.iter().enumerate() {
+            if i + 1 == self.rows.len() {
+                layout.write_last_row(row, writer)?;
+            } else {
+                layout.write_row(row, writer)?;
+            }
         }
         //Finish
-        writer.write(XmlWriterEvent::end_element())
+        layout.check_spans_ended()?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
     }
 
-    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
-        vec![
-            ("table:style-name", self.style_name.as_deref()),
-        ]
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        statistics.add_table();
+        for row in self.header_rows.iter().chain(&self.rows) {
+            row.update_statistics(statistics);
+        }
     }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        for row in self.header_rows.iter_mut().chain(&mut self.rows) {
+            row.number_notes(numbering);
+        }
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
+        if let Some(style) = self.automatic_style() {
+            stylesheet.add_automatic_style(style);
+        }
+        if let Some(ref preset) = self.preset {
+            for style in preset.styles() {
+                stylesheet.add_automatic_style(style);
+            }
+        }
+        for column in &self.columns {
+            if let Some(style) = column.automatic_style() {
+                stylesheet.add_automatic_style(style);
+            }
+        }
+        for row in self.header_rows.iter().chain(&self.rows) {
+            row.register_automatic_styles(stylesheet);
+        }
+    }
+
+    fn is_block(&self) -> bool {
+        true
+    }
+}
+
+/// Horizontal alignment of a table (`table:align`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, AsRefStr)]
+#[strum(serialize_all = "kebab-case")]
+pub enum TableAlignment {
+    Left,
+    Center,
+    Right,
+    /// Fill the space between the page margins
+    Margins,
+}
+
+/// Prefix of the names of the automatic styles generated for tables, reserved so that they do
+/// not replace the styles of the stylesheet
+pub(crate) const AUTOMATIC_STYLE_PREFIX: &str = "odtgen_";
+
+/// Name of an automatic style, derived from its properties so that identical styles are shared.
+///
+/// The properties are kept readable in the name, followed by a hash of their exact values so
+/// that different properties never give the same name.
+fn automatic_style_name(kind: &str, parts: &[Option<&str>]) -> String {
+    //FNV-1a, stable across runs and platforms
+    let mut hash: u32 = 0x811c9dc5;
+    let mut name = format!("{}{}", AUTOMATIC_STYLE_PREFIX, kind);
+    for part in parts {
+        let bytes = match part {
+            Some(part) => {
+                name.push('_');
+                name.extend(part.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }));
+                [&[1], part.as_bytes(), &[0]].concat()
+            }
+            None => vec![2],
+        };
+        for byte in bytes {
+            hash = (hash ^ byte as u32).wrapping_mul(0x01000193);
+        }
+    }
+    format!("{}_{:08x}", name, hash)
+}
+
+/// Automatic style with a single property group
+fn automatic_style(
+    name: String,
+    family: StyleFamily,
+    parent_style_name: Option<&str>,
+    group: StylePropertyGroup,
+    properties: StyleItem,
+) -> Style {
+    let mut style = Style::new(name, family);
+    style.automatic = true;
+    style.parent_style_name = parent_style_name.map(str::to_string);
+    style.properties.insert(group, properties);
+    style
 }
 
 // ===============================================================================================
@@ -64,6 +231,10 @@ pub struct TableColumn {
     pub style_name: Option<String>,
     pub default_cell_style_name: Option<String>,
     pub number_columns_repeated: Option<u32>,
+    /// Absolute width (e.g. `3cm`), written in an automatic column style
+    pub width: Option<String>,
+    /// Width relative to the other columns, written in an automatic column style
+    pub relative_width: Option<u32>,
 }
 
 impl TableColumn {
@@ -71,7 +242,9 @@ impl TableColumn {
         Self {
             style_name: None,
             default_cell_style_name: None,
-            number_columns_repeated: None
+            number_columns_repeated: None,
+            width: None,
+            relative_width: None,
         }
     }
 
@@ -81,25 +254,68 @@ impl TableColumn {
         col
     }
 
+    /// Helper to create a column with an absolute width (e.g. `3cm`).
+    pub fn with_width<T: Into<String>>(width: T) -> Self {
+        let mut col = TableColumn::new();
+        col.width = Some(width.into());
+        col
+    }
+
+    /// Helper to create a column with a width relative to the other columns
+    /// (e.g. `2` is twice as wide as `1`).
+    pub fn with_relative_width(relative_width: u32) -> Self {
+        let mut col = TableColumn::new();
+        col.relative_width = Some(relative_width);
+        col
+    }
+
+    /// Automatic style holding the width of the column (based on `style_name`)
+    fn automatic_style(&self) -> Option<Style> {
+        if self.width.is_none() && self.relative_width.is_none() {
+            return None;
+        }
+        let name = automatic_style_name("Column", &[
+            self.style_name.as_deref(),
+            self.width.as_deref(),
+            self.relative_width.map(|w| format!("rel{}", w)).as_deref(),
+        ]);
+        let mut properties = StyleItem::default();
+        if let Some(ref width) = self.width {
+            properties.set("style:column-width", width);
+        }
+        if let Some(relative_width) = self.relative_width {
+            properties.set("style:rel-column-width", &format!("{}*", relative_width));
+        }
+        Some(automatic_style(name, StyleFamily::TableColumn, self.style_name.as_deref(), StylePropertyGroup::TableColumnProperties, properties))
+    }
+}
+
+impl Default for TableColumn {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FlatOdtXmlWrite for TableColumn {
-    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
         let attr : String;
+        let style_name = self.automatic_style().map(|s| s.name().to_string()).or_else(|| self.style_name.clone());
         let mut start = XmlWriterEvent::start_element("table:table-column");
+        if let Some(ref style_name) = style_name {
+            start = start.attr("table:style-name", style_name);
+        }
         start = self.add_optional_string_attributes(start);
         if let Some(ref repeat) = self.number_columns_repeated {
             attr = repeat.to_string();
             start = start.attr("table:number-columns-repeated", attr.as_str());
         }
         writer.write(start)?;
-        writer.write(XmlWriterEvent::end_element())
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
     }
 
-    //noinspection DuplicatedCode
     fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
         vec![
-            ("table:style-name", self.style_name.as_deref()),
             ("table:default-cell-style-name", self.default_cell_style_name.as_deref()),
         ]
     }
@@ -131,20 +347,34 @@ impl TableRow {
     }
 }
 
+impl Default for TableRow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FlatOdtXmlWrite for TableRow {
-    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
-        let attr : String;
-        let mut start = XmlWriterEvent::start_element("table:table-row");
-        start = self.add_optional_string_attributes(start);
-        if let Some(ref repeat) = self.number_rows_repeated {
-            attr = repeat.to_string();
-            start = start.attr("table:number-rows-repeated", attr.as_str());
+    /// Write the row alone (cells spanning several rows are not covered in the following rows).
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        TableLayout::default().write_row(self, writer)
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        for cell in &self.cells {
+            cell.update_statistics(statistics);
         }
-        writer.write(start)?;
+    }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        for cell in &mut self.cells {
+            cell.number_notes(numbering);
+        }
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
         for cell in &self.cells {
-            cell.write_flat_odt_xml(writer)?;
+            cell.register_automatic_styles(stylesheet);
         }
-        writer.write(XmlWriterEvent::end_element())
     }
 
     //noinspection DuplicatedCode
@@ -156,13 +386,166 @@ impl FlatOdtXmlWrite for TableRow {
     }
 }
 
+/// Positions covered by cells spanning several rows, tracked while writing the rows of a table
+#[derive(Default)]
+pub(crate) struct TableLayout {
+    table_name: String,
+    /// Number of columns the rows must fill (not checked when the table has no column specs)
+    column_count: Option<usize>,
+    /// Number of following rows in which each column position is covered
+    covered: Vec<u32>,
+    row_count: usize,
+    preset: Option<TablePreset>,
+    body_row_count: usize,
+}
+
+impl TableLayout {
+    fn new(table: &Table) -> Self {
+        TableLayout {
+            table_name: table.name.clone(),
+            column_count: Some(table.column_count()).filter(|count| *count > 0),
+            covered: Vec::new(),
+            row_count: 0,
+            preset: table.preset,
+            body_row_count: 0,
+        }
+    }
+
+    /// Write a header row (see `write_row`).
+    pub(crate) fn write_header_row(&mut self, row: &TableRow, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        self.write_row_of_kind(row, RowKind::Header, writer)
+    }
+
+    /// Write a body row, adding the covered cells of spanned positions.
+    ///
+    /// Fails, writing nothing, if the row does not fill the columns of the table or if its
+    /// cells overlap the ones spanning rows from the previous rows.
+    pub(crate) fn write_row(&mut self, row: &TableRow, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let kind = RowKind::Body { index: self.body_row_count, last: false };
+        self.body_row_count += 1;
+        self.write_row_of_kind(row, kind, writer)
+    }
+
+    /// Write the last row of the table (see `write_row`).
+    pub(crate) fn write_last_row(&mut self, row: &TableRow, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        let kind = RowKind::Body { index: self.body_row_count, last: true };
+        self.body_row_count += 1;
+        self.write_row_of_kind(row, kind, writer)
+    }
+
+    fn write_row_of_kind(&mut self, row: &TableRow, kind: RowKind, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        //The row is checked before being written, so that a rejected row leaves no output
+        let (slots, covered) = self.layout_row(row)?;
+
+        let attr : String;
+        let preset_row_style = self.preset.and_then(|p| p.row_style_name(kind)).filter(|_| row.style_name.is_none());
+        let preset_cell_style = self.preset.map(|p| p.cell_style_name(kind));
+        let mut start = XmlWriterEvent::start_element("table:table-row");
+        if let Some(ref style_name) = preset_row_style {
+            start = start.attr("table:style-name", style_name);
+        }
+        start = row.add_optional_string_attributes(start);
+        if let Some(ref repeat) = row.number_rows_repeated {
+            attr = repeat.to_string();
+            start = start.attr("table:number-rows-repeated", attr.as_str());
+        }
+        writer.write(start)?;
+        for slot in slots {
+            match slot {
+                Some(cell) => cell.write_with_default_style(preset_cell_style.as_deref(), writer)?,
+                None => TableLayout::write_covered_cell(writer)?,
+            }
+        }
+        writer.write(XmlWriterEvent::end_element())?;
+
+        self.covered = covered;
+        self.row_count += 1;
+        Ok(())
+    }
+
+    /// Place the cells of a row, returning the cells by column position (`None` for covered
+    /// positions) and the positions covered in the following rows.
+    fn layout_row<'a>(&self, row: &'a TableRow) -> Result<(Vec<Option<&'a TableCell>>, Vec<u32>)> {
+        let error = |message: String| Error::Table(format!(
+            "Table '{}', row {}: {}!", self.table_name, self.row_count + 1, message
+        ));
+        let repeated = row.number_rows_repeated.unwrap_or(1).max(1);
+        let mut covered = self.covered.clone();
+        let mut slots = Vec::new();
+        let mut cells = row.cells.iter();
+        loop {
+            //Positions covered by cells of the previous rows
+            let position = slots.len();
+            if let Some(remaining) = covered.get_mut(position) && *remaining > 0 {
+                if *remaining < repeated {
+                    return Err(error("a repeated row is partly covered by a cell spanning several rows".to_string()));
+                }
+                *remaining -= repeated;
+                slots.push(None);
+                continue;
+            }
+            let Some(cell) = cells.next() else { break };
+            if !cell.content.contains_only_blocks() {
+                return Err(error("a cell contains inline content (text, span, image...) outside of a paragraph".to_string()));
+            }
+            let columns = cell.number_columns_spanned.unwrap_or(1).max(1) as usize;
+            let rows = cell.number_rows_spanned.unwrap_or(1).max(1);
+            if rows > 1 && repeated > 1 {
+                return Err(error("a repeated row cannot contain cells spanning several rows".to_string()));
+            }
+            if covered.len() < position + columns {
+                covered.resize(position + columns, 0);
+            }
+            if covered[position..position + columns].iter().any(|c| *c > 0) {
+                return Err(error("a cell spanning several columns overlaps a cell spanning several rows".to_string()));
+            }
+            for c in &mut covered[position..position + columns] {
+                *c = rows - 1;
+            }
+            slots.push(Some(cell));
+            slots.extend((1..columns).map(|_| None));
+        }
+        let position = slots.len();
+        for c in covered.iter_mut().skip(position) {
+            *c = c.saturating_sub(repeated);
+        }
+        if let Some(column_count) = self.column_count && position != column_count {
+            return Err(error(format!("the cells fill {} columns instead of {}", position, column_count)));
+        }
+        Ok((slots, covered))
+    }
+
+    /// Check that no cell spans rows past the last written row (e.g. at the end of the table).
+    pub(crate) fn check_spans_ended(&self) -> Result<()> {
+        match self.covered.iter().max() {
+            Some(remaining) if *remaining > 0 => Err(Error::Table(format!(
+                "Table '{}', row {}: a cell spans {} rows past the end of its table or header rows!",
+                self.table_name, self.row_count, remaining
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn write_covered_cell(writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        writer.write(XmlWriterEvent::start_element("table:covered-table-cell"))?;
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+}
+
 // ===============================================================================================
 // Cells
 // ===============================================================================================
 
 pub struct TableCell {
     pub style_name: Option<String>,
-    pub value_type: Option<String>,
+    /// Number of columns merged into this cell (the covered cells are written automatically)
+    pub number_columns_spanned: Option<u32>,
+    /// Number of rows merged into this cell (the covered cells are written automatically)
+    pub number_rows_spanned: Option<u32>,
+    /// Typed value (`office:value-type` and value attributes), independent of the displayed content
+    pub value: Option<CellValue>,
+    /// Blocks only (paragraphs, headings, lists, nested tables), checked when the table is written
     pub content: Container,
 }
 
@@ -170,7 +553,9 @@ impl TableCell {
     pub fn new() -> Self {
         Self {
             style_name: None,
-            value_type: None,
+            number_columns_spanned: None,
+            number_rows_spanned: None,
+            value: None,
             content: Container::new()
         }
     }
@@ -181,22 +566,441 @@ impl TableCell {
         cell
     }
 
+    /// Merge the cell with the following cells of its row (`columns`) and of the following
+    /// rows (`rows`). The next cells of these rows are placed after the merged area.
+    pub fn set_span(&mut self, columns: u32, rows: u32) {
+        self.number_columns_spanned = Some(columns).filter(|c| *c > 1);
+        self.number_rows_spanned = Some(rows).filter(|r| *r > 1);
+    }
+
+    /// Create a cell with a typed value, displayed in a default paragraph.
+    ///
+    /// ODF has no NaN or infinite number: such a number is only kept as the text of the cell.
+    pub fn from_value(value: CellValue) -> Self {
+        let mut cell = TableCell::new();
+        cell.content.add(Paragraph::from_text(value.display_text()));
+        cell.value = Some(value).filter(CellValue::is_finite);
+        cell
+    }
+
+    /// Helper to create a cell with a number (`float`).
+    pub fn float(value: f64) -> Self {
+        TableCell::from_value(CellValue::Float(value))
+    }
 
+    /// Helper to create a cell with a percentage (`0.5` is displayed as `50%`).
+    pub fn percentage(value: f64) -> Self {
+        TableCell::from_value(CellValue::Percentage(value))
+    }
+
+    /// Helper to create a cell with an amount of money in the given currency (e.g. `EUR`).
+    pub fn currency<T: Into<String>>(value: f64, currency: T) -> Self {
+        TableCell::from_value(CellValue::Currency { value, currency: currency.into() })
+    }
+
+    /// Helper to create a cell with a date.
+    pub fn date(date: NaiveDate) -> Self {
+        TableCell::from_value(CellValue::Date(date.and_time(NaiveTime::MIN)))
+    }
+
+    /// Helper to create a cell with a date and time.
+    pub fn date_time(date_time: NaiveDateTime) -> Self {
+        TableCell::from_value(CellValue::Date(date_time))
+    }
+
+    /// Helper to create a cell with a duration (`time`).
+    pub fn time(time: Duration) -> Self {
+        TableCell::from_value(CellValue::Time(time))
+    }
+
+    /// Helper to create a cell with a boolean.
+    pub fn boolean(value: bool) -> Self {
+        TableCell::from_value(CellValue::Boolean(value))
+    }
 }
 
-impl FlatOdtXmlWrite for TableCell {
-    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
+impl Default for TableCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TableCell {
+    /// Write the cell, with a style used when it has none (e.g. from a table preset).
+    fn write_with_default_style(&self, default_style_name: Option<&str>, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
         let mut start = XmlWriterEvent::start_element("table:table-cell");
-        start = self.add_optional_string_attributes(start);
+        if let Some(style_name) = self.style_name.as_deref().or(default_style_name) {
+            start = start.attr("table:style-name", style_name);
+        }
+        let spans: Vec<(&str, String)> = [
+            ("table:number-columns-spanned", self.number_columns_spanned),
+            ("table:number-rows-spanned", self.number_rows_spanned),
+        ].into_iter()
+            .filter_map(|(key, span)| span.map(|s| (key, s.to_string())))
+            .collect();
+        for (key, value) in &spans {
+            start = start.attr(*key, value.as_str());
+        }
+        let value_attributes = self.value.as_ref().map(CellValue::attributes).unwrap_or_default();
+        for (key, value) in &value_attributes {
+            start = start.attr(*key, value.as_str());
+        }
         writer.write(start)?;
         self.content.write_flat_odt_xml(writer)?;
-        writer.write(XmlWriterEvent::end_element())
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
     }
+}
 
-    fn optional_string_attributes(&self) -> Vec<(&'static str, Option<&str>)> {
-        vec![
-            ("table:style-name", self.style_name.as_deref()),
-            ("office:value-type", self.value_type.as_deref()),
-        ]
+impl FlatOdtXmlWrite for TableCell {
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        self.write_with_default_style(None, writer)
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        self.content.update_statistics(statistics);
+    }
+
+    fn number_notes(&mut self, numbering: &mut NoteNumbering) {
+        self.content.number_notes(numbering);
+    }
+
+    fn register_automatic_styles(&self, stylesheet: &mut Stylesheet) {
+        self.content.register_automatic_styles(stylesheet);
+    }
+}
+
+/// Typed value of a table cell, kept as a value when the table is copied into a spreadsheet
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Float(f64),
+    /// Fraction (`0.5` for 50%)
+    Percentage(f64),
+    Currency {
+        value: f64,
+        /// ISO 4217 code (e.g. `EUR`)
+        currency: String,
+    },
+    /// Date, written without time when at midnight
+    Date(NaiveDateTime),
+    Time(Duration),
+    Boolean(bool),
+    String(String),
+}
+
+impl CellValue {
+    fn value_type(&self) -> &'static str {
+        match self {
+            CellValue::Float(_) => "float",
+            CellValue::Percentage(_) => "percentage",
+            CellValue::Currency { .. } => "currency",
+            CellValue::Date(_) => "date",
+            CellValue::Time(_) => "time",
+            CellValue::Boolean(_) => "boolean",
+            CellValue::String(_) => "string",
+        }
+    }
+
+    /// Whether the value can be written as a value, numbers being finite in ODF
+    fn is_finite(&self) -> bool {
+        match self {
+            CellValue::Float(v) | CellValue::Percentage(v) | CellValue::Currency { value: v, .. } => v.is_finite(),
+            _ => true,
+        }
+    }
+
+    /// Attributes of the cell (`office:value-type` and the value), a `string` for a number that
+    /// is not finite
+    fn attributes(&self) -> Vec<(&'static str, String)> {
+        if !self.is_finite() {
+            return vec![("office:value-type", "string".to_string())];
+        }
+        let mut attributes = vec![("office:value-type", self.value_type().to_string())];
+        match self {
+            CellValue::Float(v) | CellValue::Percentage(v) => attributes.push(("office:value", v.to_string())),
+            CellValue::Currency { value, currency } => {
+                attributes.push(("office:currency", currency.clone()));
+                attributes.push(("office:value", value.to_string()));
+            }
+            CellValue::Date(d) => attributes.push(("office:date-value", CellValue::format_date(d))),
+            CellValue::Time(t) => attributes.push(("office:time-value", format_duration(t))),
+            CellValue::Boolean(b) => attributes.push(("office:boolean-value", b.to_string())),
+            CellValue::String(_) => {}
+        }
+        attributes
+    }
+
+    /// Text of the default paragraph displaying the value
+    pub fn display_text(&self) -> String {
+        match self {
+            CellValue::Float(v) => v.to_string(),
+            CellValue::Percentage(v) => format!("{}%", CellValue::format_number(v * 100.0)),
+            CellValue::Currency { value, currency } => format!("{:.2} {}", value, currency),
+            CellValue::Date(d) => CellValue::format_date(d).replace('T', " "),
+            CellValue::Time(t) => {
+                let seconds = t.as_secs();
+                format!("{:02}:{:02}:{:02}", seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+            }
+            CellValue::Boolean(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+            CellValue::String(s) => s.clone(),
+        }
+    }
+
+    fn format_date(date: &NaiveDateTime) -> String {
+        if date.time() == NaiveTime::MIN {
+            date.format("%Y-%m-%d").to_string()
+        } else {
+            date.format("%Y-%m-%dT%H:%M:%S").to_string()
+        }
+    }
+
+    /// Format a computed number without floating point noise (e.g. `0.07 * 100.0`).
+    fn format_number(value: f64) -> String {
+        let text = format!("{:.10}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xml::writer::EmitterConfig;
+    use super::*;
+
+    fn write<T: FlatOdtXmlWrite>(element: &T) -> Result<String> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut out as &mut dyn Write);
+        element.write_flat_odt_xml(&mut writer)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn cell(text: &str) -> TableCell {
+        let mut cell = TableCell::new();
+        cell.content.add(Paragraph::from_text(text));
+        cell
+    }
+
+    fn spanned_cell(text: &str, columns: u32, rows: u32) -> TableCell {
+        let mut cell = cell(text);
+        cell.set_span(columns, rows);
+        cell
+    }
+
+    fn row(cells: Vec<TableCell>) -> TableRow {
+        let mut row = TableRow::new();
+        for cell in cells {
+            row.add_cell(cell);
+        }
+        row
+    }
+
+    fn table(column_count: usize, rows: Vec<TableRow>) -> Table {
+        let mut table = Table::new("T");
+        for _ in 0..column_count {
+            table.add_column(TableColumn::new());
+        }
+        for row in rows {
+            table.add_row(row);
+        }
+        table
+    }
+
+    /// Elements of the rows: `C` for a cell, `-` for a covered cell
+    fn cell_layout(xml: &str) -> Vec<String> {
+        xml.split("<table:table-row").skip(1)
+            .map(|row| row.match_indices("<table:").map(|(i, _)| &row[i..])
+                .filter_map(|e| if e.starts_with("<table:table-cell") {
+                    Some('C')
+                } else if e.starts_with("<table:covered-table-cell") {
+                    Some('-')
+                } else {
+                    None
+                })
+                .collect())
+            .collect()
+    }
+
+    #[test]
+    fn column_spans_are_followed_by_covered_cells() {
+        let table = table(3, vec![
+            row(vec![spanned_cell("A", 2, 1), cell("B")]),
+            row(vec![cell("C"), cell("D"), cell("E")]),
+        ]);
+        let xml = write(&table).unwrap();
+        assert!(xml.contains("table:number-columns-spanned=\"2\""));
+        assert_eq!(cell_layout(&xml), ["C-C", "CCC"]);
+    }
+
+    #[test]
+    fn row_spans_cover_the_following_rows() {
+        let table = table(2, vec![
+            row(vec![spanned_cell("A", 1, 3), cell("B")]),
+            row(vec![cell("C")]),
+            row(vec![cell("D")]),
+            row(vec![cell("E"), cell("F")]),
+        ]);
+        let xml = write(&table).unwrap();
+        assert!(xml.contains("table:number-rows-spanned=\"3\""));
+        assert_eq!(cell_layout(&xml), ["CC", "-C", "-C", "CC"]);
+    }
+
+    #[test]
+    fn rows_not_filling_the_columns_are_rejected_before_being_written() {
+        let mut layout = TableLayout::new(&table(2, Vec::new()));
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut out as &mut dyn Write);
+        assert!(layout.write_row(&row(vec![cell("A"), cell("B"), cell("C")]), &mut writer).is_err());
+        assert!(layout.write_row(&row(vec![cell("A")]), &mut writer).is_err());
+        drop(writer);
+        assert!(out.is_empty());
+
+        assert!(write(&table(2, vec![row(vec![spanned_cell("A", 2, 1), cell("B")])])).is_err());
+    }
+
+    #[test]
+    fn row_spans_past_the_last_row_are_rejected() {
+        let spanning = table(2, vec![
+            row(vec![spanned_cell("A", 1, 3), cell("B")]),
+            row(vec![cell("C")]),
+        ]);
+        assert!(write(&spanning).is_err());
+
+        let mut header = table(1, vec![row(vec![cell("B")])]);
+        header.add_header_row(row(vec![spanned_cell("A", 1, 2)]));
+        assert!(write(&header).is_err());
+    }
+
+    #[test]
+    fn overlapping_spans_are_rejected() {
+        let table = table(2, vec![
+            row(vec![cell("A"), spanned_cell("B", 1, 2)]),
+            row(vec![spanned_cell("C", 2, 1)]),
+        ]);
+        assert!(write(&table).is_err());
+    }
+
+    #[test]
+    fn repeated_rows_with_row_spans_are_rejected() {
+        let mut repeated = row(vec![spanned_cell("A", 1, 2)]);
+        repeated.number_rows_repeated = Some(2);
+        assert!(write(&table(1, vec![repeated, row(vec![cell("B")])])).is_err());
+
+        let mut partly_covered = row(vec![cell("B")]);
+        partly_covered.number_rows_repeated = Some(3);
+        let table = table(2, vec![
+            row(vec![spanned_cell("A", 1, 2), cell("C")]),
+            partly_covered,
+        ]);
+        assert!(write(&table).is_err());
+    }
+
+    #[test]
+    fn repeated_rows_fully_covered_by_a_span_are_accepted() {
+        let mut repeated = row(vec![cell("B")]);
+        repeated.number_rows_repeated = Some(2);
+        let table = table(2, vec![
+            row(vec![spanned_cell("A", 1, 3), cell("C")]),
+            repeated,
+        ]);
+        assert_eq!(cell_layout(&write(&table).unwrap()), ["CC", "-C"]);
+    }
+
+    #[test]
+    fn automatic_style_names_do_not_collide() {
+        let names = [
+            automatic_style_name("Table", &[Some("a b"), None]),
+            automatic_style_name("Table", &[Some("a_b"), None]),
+            automatic_style_name("Table", &[Some("x"), None]),
+            automatic_style_name("Table", &[None, Some("x")]),
+        ];
+        for (i, name) in names.iter().enumerate() {
+            assert!(name.starts_with(AUTOMATIC_STYLE_PREFIX));
+            assert!(!names[i + 1..].contains(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn user_styles_are_not_replaced_by_automatic_styles() {
+        let mut table = Table::new("T");
+        table.width = Some("16cm".to_string());
+        let name = table.automatic_style().unwrap().name().to_string();
+        let mut stylesheet = Stylesheet::new();
+        stylesheet.add_style(Style::new(name.clone(), StyleFamily::Paragraph));
+        stylesheet.add_style(Style::new("Table_16cm".to_string(), StyleFamily::Table));
+        table.register_automatic_styles(&mut stylesheet);
+        let user_style = stylesheet.styles().find(|s| s.name() == name).unwrap();
+        assert!(!user_style.automatic);
+        assert!(stylesheet.styles().find(|s| s.name() == "Table_16cm").is_some_and(|s| !s.automatic));
+    }
+
+    #[test]
+    fn tables_in_notes_register_their_styles() {
+        let mut table = table(1, vec![row(vec![cell("A")])]);
+        table.width = Some("10cm".to_string());
+        let name = table.automatic_style().unwrap().name().to_string();
+        let mut note = crate::note::Note::footnote("Note");
+        note.body.add(table);
+        let mut paragraph = Paragraph::from_text("Text");
+        paragraph.content.add(note);
+        let mut stylesheet = Stylesheet::new();
+        paragraph.register_automatic_styles(&mut stylesheet);
+        assert!(stylesheet.contains(&name));
+    }
+
+    #[test]
+    fn nested_tables_are_written_inside_cells() {
+        let mut nested_cell = TableCell::new();
+        nested_cell.content.add(table(1, vec![row(vec![cell("Inner")])]));
+        let outer = table(2, vec![row(vec![cell("Outer"), nested_cell])]);
+        let xml = write(&outer).unwrap();
+        let nested = xml.find("<table:table-cell><table:table table:name=\"T\">")
+            .expect("nested table directly in the cell");
+        assert!(xml[nested..].contains("Inner"));
+        assert_eq!(xml.matches("<table:table ").count(), 2);
+    }
+
+    #[test]
+    fn inline_content_in_cells_is_rejected_before_the_row_is_written() {
+        let mut inline_cell = TableCell::new();
+        inline_cell.content.add(crate::text::Text { text: "Inline".to_string() });
+        let mut layout = TableLayout::new(&table(1, Vec::new()));
+        let mut out: Vec<u8> = Vec::new();
+        let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut out as &mut dyn Write);
+        assert!(layout.write_row(&row(vec![inline_cell]), &mut writer).is_err());
+        drop(writer);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn raw_elements_in_cells_must_be_blocks() {
+        use crate::raw_xml::{RawXml, RawXmlNode};
+        let raw_cell = |xml: &str| {
+            let root = RawXml::read_document(format!(
+                "<r xmlns:text=\"urn:oasis:names:tc:opendocument:xmlns:text:1.0\">{}</r>", xml
+            ).as_bytes()).unwrap();
+            let mut cell = TableCell::new();
+            for child in root.into_children() {
+                if let RawXmlNode::Element(e) = child {
+                    cell.content.add(e);
+                }
+            }
+            cell
+        };
+        assert!(write(&table(1, vec![row(vec![raw_cell("<text:p>Raw</text:p>")])])).is_ok());
+        assert!(write(&table(1, vec![row(vec![raw_cell("<text:section text:name=\"S\"/>")])])).is_ok());
+        assert!(write(&table(1, vec![row(vec![raw_cell("<text:bookmark text:name=\"B\"/>")])])).is_err());
+    }
+
+    #[test]
+    fn numbers_that_are_not_finite_are_written_as_text() {
+        let nan = write(&TableCell::float(f64::NAN)).unwrap();
+        assert!(!nan.contains("office:value"), "{}", nan);
+        assert!(nan.contains("NaN"));
+        assert!(!write(&TableCell::currency(f64::INFINITY, "EUR")).unwrap().contains("office:value="));
+        let mut cell = cell("100%");
+        cell.value = Some(CellValue::Percentage(f64::NEG_INFINITY));
+        let xml = write(&cell).unwrap();
+        assert!(xml.contains("office:value-type=\"string\""), "{}", xml);
+        assert!(!xml.contains("office:value="));
+        assert!(write(&TableCell::percentage(0.5)).unwrap().contains("office:value=\"0.5\""));
     }
 }
@@ -0,0 +1,387 @@
+//! Creation of tables from CSV files, row iterators and records
+//!
+//! One column is declared per value of the longest row, shorter rows being completed with
+//! empty cells. Numeric values (e.g. `12.5`, `-3`, `7%`) are written as typed cells, keeping
+//! the original text as displayed content.
+//!
+//! Records are values implementing `OdtTableRow`, usually derived with `#[derive(OdtTableRow)]`
+//! (`derive` feature).
+
+use std::fmt::Display;
+use std::io::Read;
+use std::time::Duration;
+use chrono::{NaiveDate, NaiveDateTime};
+use crate::error::{Error, Result};
+use crate::paragraph::Paragraph;
+use crate::table::{CellValue, Table, TableCell, TableColumn, TableRow};
+
+/// A record written as a table row, one field per column
+///
+/// The derive macro accepts the following field attributes:
+/// * `#[odt(header = "...")]`: label of the column (the field name by default),
+/// * `#[odt(column_style = "...")]`: style of the column,
+/// * `#[odt(width = "...")]`: width of the column (e.g. `3cm`),
+/// * `#[odt(skip)]`: field not written.
+pub trait OdtTableRow {
+    /// Labels of the header row
+    fn headers() -> Vec<String>;
+
+    /// Column specs of the table
+    fn columns() -> Vec<TableColumn> {
+        Self::headers().iter().map(|_| TableColumn::new()).collect()
+    }
+
+    /// Cells of the row, in the order of the headers
+    fn cells(&self) -> Vec<TableCell>;
+}
+
+/// Conversion of a field value into a (typed) table cell
+pub trait ToTableCell {
+    fn to_table_cell(&self) -> TableCell;
+}
+
+/// Numbers are written as `float` cells, displaying the exact number.
+///
+/// The value is an `f64`: 64-bit integers beyond 2^53 are rounded to the nearest `f64`, and
+/// `f32` numbers are converted through their shortest text (`0.1f32` gives `0.1`). NaN and
+/// infinite numbers, which ODF cannot hold, are string cells.
+macro_rules! number_to_table_cell {
+    ($to_f64:expr; $($t:ty),*) => {
+        $(impl ToTableCell for $t {
+            fn to_table_cell(&self) -> TableCell {
+                let to_f64 = $to_f64;
+                let mut cell = TableCell::new();
+                cell.content.add(Paragraph::from_text(self.to_string()));
+                let value: f64 = to_f64(*self);
+                if value.is_finite() {
+                    cell.value = Some(CellValue::Float(value));
+                }
+                cell
+            }
+        })*
+    };
+}
+
+number_to_table_cell!(|n| n as f64; i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+number_to_table_cell!(|n| n; f64);
+number_to_table_cell!(|n: f32| n.to_string().parse().unwrap_or(n as f64); f32);
+
+impl ToTableCell for bool {
+    fn to_table_cell(&self) -> TableCell {
+        TableCell::boolean(*self)
+    }
+}
+
+impl ToTableCell for str {
+    fn to_table_cell(&self) -> TableCell {
+        let mut cell = TableCell::new();
+        cell.content.add(Paragraph::from_text(self));
+        cell
+    }
+}
+
+impl ToTableCell for String {
+    fn to_table_cell(&self) -> TableCell {
+        self.as_str().to_table_cell()
+    }
+}
+
+impl ToTableCell for NaiveDate {
+    fn to_table_cell(&self) -> TableCell {
+        TableCell::date(*self)
+    }
+}
+
+impl ToTableCell for NaiveDateTime {
+    fn to_table_cell(&self) -> TableCell {
+        TableCell::date_time(*self)
+    }
+}
+
+impl ToTableCell for Duration {
+    fn to_table_cell(&self) -> TableCell {
+        TableCell::time(*self)
+    }
+}
+
+impl ToTableCell for CellValue {
+    fn to_table_cell(&self) -> TableCell {
+        TableCell::from_value(self.clone())
+    }
+}
+
+/// `None` is written as an empty cell
+impl<T: ToTableCell> ToTableCell for Option<T> {
+    fn to_table_cell(&self) -> TableCell {
+        match self {
+            Some(value) => value.to_table_cell(),
+            None => {
+                let mut cell = TableCell::new();
+                cell.content.add(Paragraph::from_text(""));
+                cell
+            }
+        }
+    }
+}
+
+impl<T: ToTableCell + ?Sized> ToTableCell for &T {
+    fn to_table_cell(&self) -> TableCell {
+        (**self).to_table_cell()
+    }
+}
+
+/// Options of `Table::from_csv` and `Table::from_rows_with_options`
+#[derive(Debug, Clone)]
+pub struct TableOptions {
+    /// Name of the table (`table:name`)
+    pub name: String,
+    /// Whether the first row is a header row, repeated at the top of each page
+    pub header: bool,
+    /// Style of the cells of the header row
+    pub header_cell_style_name: Option<String>,
+    /// Whether numeric values are written as typed cells (`float` or `percentage`)
+    pub detect_numbers: bool,
+    /// Field delimiter of CSV files
+    pub delimiter: u8,
+}
+
+impl TableOptions {
+    fn header_cell(&self, text: &str) -> TableCell {
+        let mut cell = match self.header_cell_style_name {
+            Some(ref style_name) => TableCell::new_with_style(style_name),
+            None => TableCell::new(),
+        };
+        cell.content.add(Paragraph::from_text(text));
+        cell
+    }
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            name: "Table1".to_string(),
+            header: false,
+            header_cell_style_name: None,
+            detect_numbers: true,
+            delimiter: b',',
+        }
+    }
+}
+
+impl Table {
+    /// Create a table from CSV data.
+    pub fn from_csv<R: Read>(reader: R, options: &TableOptions) -> Result<Table> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(options.delimiter)
+            .from_reader(reader);
+        let mut records = Vec::new();
+        for record in reader.records() {
+            records.push(record.map_err(csv_error)?);
+        }
+        Ok(Table::from_rows_with_options(&records, options))
+    }
+
+    /// Create a table from rows of values, with the default options.
+    pub fn from_rows<I, R, T>(rows: I) -> Table
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = T>,
+        T: Display,
+    {
+        Table::from_rows_with_options(rows, &TableOptions::default())
+    }
+
+    /// Create a table from rows of values.
+    ///
+    /// Without any value, the table has a single empty cell, as a table needs a column and a row.
+    pub fn from_rows_with_options<I, R, T>(rows: I, options: &TableOptions) -> Table
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = T>,
+        T: Display,
+    {
+        let rows: Vec<Vec<String>> = rows.into_iter()
+            .map(|row| row.into_iter().map(|value| value.to_string()).collect())
+            .collect();
+        let column_count = rows.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+        let mut table = Table::new(&options.name);
+        for _ in 0..column_count {
+            table.add_column(TableColumn::new());
+        }
+        let mut rows = rows.into_iter();
+        if options.header && let Some(values) = rows.next() {
+            let mut row = TableRow::new();
+            for i in 0..column_count {
+                row.add_cell(options.header_cell(values.get(i).map_or("", String::as_str)));
+            }
+            table.add_header_row(row);
+        }
+        for values in rows {
+            let mut row = TableRow::new();
+            for i in 0..column_count {
+                let text = values.get(i).map_or("", String::as_str);
+                let mut cell = TableCell::new();
+                if options.detect_numbers {
+                    cell.value = parse_number(text);
+                }
+                cell.content.add(Paragraph::from_text(text));
+                row.add_cell(cell);
+            }
+            table.add_row(row);
+        }
+        table.add_empty_row_if_empty();
+        table
+    }
+
+    /// Create a table from records, with a header row.
+    pub fn from_records<T: OdtTableRow>(records: &[T]) -> Table {
+        let options = TableOptions {
+            header: true,
+            ..TableOptions::default()
+        };
+        Table::from_records_with_options(records, &options)
+    }
+
+    /// Create a table from records (`detect_numbers` and `delimiter` are not used).
+    ///
+    /// Without header nor record, the table has a row of empty cells, as a table needs a row.
+    pub fn from_records_with_options<T: OdtTableRow>(records: &[T], options: &TableOptions) -> Table {
+        let mut table = Table::new(&options.name);
+        for column in T::columns() {
+            table.add_column(column);
+        }
+        if options.header {
+            let mut row = TableRow::new();
+            for header in T::headers() {
+                row.add_cell(options.header_cell(&header));
+            }
+            table.add_header_row(row);
+        }
+        for record in records {
+            let mut row = TableRow::new();
+            for cell in record.cells() {
+                row.add_cell(cell);
+            }
+            table.add_row(row);
+        }
+        table.add_empty_row_if_empty();
+        table
+    }
+
+    /// Add a row of empty cells (and a column if needed) to a table without any row.
+    fn add_empty_row_if_empty(&mut self) {
+        if !self.header_rows.is_empty() || !self.rows.is_empty() {
+            return;
+        }
+        if self.column_count() == 0 {
+            self.add_column(TableColumn::new());
+        }
+        let mut row = TableRow::new();
+        for _ in 0..self.column_count() {
+            let mut cell = TableCell::new();
+            cell.content.add(Paragraph::from_text(""));
+            row.add_cell(cell);
+        }
+        self.add_row(row);
+    }
+}
+
+/// Typed value of a numeric text (`12.5`, `-3`, `1e3`, `7%`), out of range numbers (`1e999`)
+/// being kept as text
+fn parse_number(text: &str) -> Option<CellValue> {
+    let text = text.trim();
+    let (number, percentage) = match text.strip_suffix('%') {
+        Some(number) => (number.trim_end(), true),
+        None => (text, false),
+    };
+    //Reject the special values accepted by `f64::from_str` (`inf`, `NaN`...)
+    if !number.chars().any(|c| c.is_ascii_digit())
+        || !number.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c)) {
+        return None;
+    }
+    let value = number.parse::<f64>().ok().filter(|value| value.is_finite())?;
+    Some(if percentage { CellValue::Percentage(value / 100.0) } else { CellValue::Float(value) })
+}
+
+fn csv_error(error: csv::Error) -> Error {
+    match error.into_kind() {
+        csv::ErrorKind::Io(e) => Error::Io(e),
+        kind => Error::Parse(format!("Invalid CSV: {:?}", kind)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_input_gives_a_single_empty_cell() {
+        for table in [Table::from_rows(Vec::<Vec<&str>>::new()), Table::from_rows(vec![Vec::<&str>::new()])] {
+            assert_eq!(table.column_count(), 1);
+            assert_eq!(table.rows.len(), 1);
+            assert_eq!(table.rows[0].cells.len(), 1);
+        }
+        let table = Table::from_csv("".as_bytes(), &TableOptions::default()).unwrap();
+        assert_eq!((table.column_count(), table.rows.len()), (1, 1));
+    }
+
+    struct Record {
+        name: &'static str,
+        count: u32,
+    }
+
+    impl OdtTableRow for Record {
+        fn headers() -> Vec<String> {
+            vec!["Name".to_string(), "Count".to_string()]
+        }
+
+        fn cells(&self) -> Vec<TableCell> {
+            vec![self.name.to_table_cell(), self.count.to_table_cell()]
+        }
+    }
+
+    #[test]
+    fn empty_records_without_header_give_a_row_of_empty_cells() {
+        let table = Table::from_records_with_options(&[] as &[Record], &TableOptions::default());
+        assert_eq!(table.column_count(), 2);
+        assert!(table.header_rows.is_empty());
+        assert_eq!(table.rows.len(), 1);
+        assert_eq!(table.rows[0].cells.len(), 2);
+        let table = Table::from_records(&[] as &[Record]);
+        assert_eq!((table.header_rows.len(), table.rows.len()), (1, 0));
+        let table = Table::from_records(&[Record { name: "A", count: 1 }]);
+        assert_eq!((table.header_rows.len(), table.rows.len()), (1, 1));
+    }
+
+    #[test]
+    fn f32_values_keep_their_decimal_text() {
+        assert_eq!(0.1f32.to_table_cell().value, Some(CellValue::Float(0.1)));
+        assert_eq!(u64::MAX.to_table_cell().value, Some(CellValue::Float(u64::MAX as f64)));
+    }
+
+    #[test]
+    fn numbers_that_are_not_finite_are_string_cells() {
+        use crate::fodt_xml_write::FlatOdtXmlWrite;
+        use xml::writer::EmitterConfig;
+        for (cell, text) in [(f64::NAN.to_table_cell(), "NaN"), (f64::INFINITY.to_table_cell(), "inf"), (f32::NEG_INFINITY.to_table_cell(), "-inf")] {
+            assert_eq!(cell.value, None);
+            let mut out: Vec<u8> = Vec::new();
+            let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut out as &mut dyn std::io::Write);
+            cell.write_flat_odt_xml(&mut writer).unwrap();
+            let xml = String::from_utf8(out).unwrap();
+            assert!(xml.contains(&format!(">{}</text:p>", text)), "{}", xml);
+            assert!(!xml.contains("office:value"));
+        }
+    }
+
+    #[test]
+    fn out_of_range_numbers_are_kept_as_text() {
+        assert_eq!(parse_number("1e999"), None);
+        assert_eq!(parse_number("-1e999%"), None);
+        assert_eq!(parse_number("1e3"), Some(CellValue::Float(1000.0)));
+        assert_eq!(parse_number("7%"), Some(CellValue::Percentage(0.07)));
+    }
+}
@@ -0,0 +1,121 @@
+//! Table presets: ready-made borders, shading and spacing for tables
+//!
+//! A preset generates automatic table, row and cell styles, applied when the table is
+//! written to the rows and cells which have no style of their own.
+//!
+//! | Preset     | Header row              | Body rows                    | Last row              |
+//! |------------|-------------------------|------------------------------|-----------------------|
+//! | `grid`     | shaded, all borders     | all borders                  | all borders           |
+//! | `booktabs` | top and bottom rules    | no borders                   | bottom rule           |
+//! | `striped`  | shaded, bottom rule     | even rows shaded             | even rows shaded      |
+
+use strum_macros::{AsRefStr, EnumString};
+use crate::style::{Style, StyleFamily, StyleItem, StylePropertyGroup};
+use crate::table::AUTOMATIC_STYLE_PREFIX;
+
+const THIN_RULE: &str = "0.5pt solid #000000";
+const THICK_RULE: &str = "1.5pt solid #000000";
+const HEADER_BACKGROUND: &str = "#dddddd";
+const STRIPE_BACKGROUND: &str = "#f2f2f2";
+
+/// Named table style preset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, AsRefStr)]
+#[strum(serialize_all = "kebab-case")]
+pub enum TablePreset {
+    /// Borders around every cell
+    Grid,
+    /// Horizontal rules only, above and below the header and below the last row
+    Booktabs,
+    /// Alternating shading of the body rows
+    Striped,
+}
+
+/// Position of a row in its table, selecting the preset styles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RowKind {
+    Header,
+    Body {
+        /// Index among the body rows (0 for the first one, an "odd" row)
+        index: usize,
+        last: bool,
+    },
+}
+
+impl TablePreset {
+    /// Prefix of the names of the generated styles
+    fn prefix(&self) -> String {
+        let name = match self {
+            TablePreset::Grid => "Grid",
+            TablePreset::Booktabs => "Booktabs",
+            TablePreset::Striped => "Striped",
+        };
+        format!("{}{}", AUTOMATIC_STYLE_PREFIX, name)
+    }
+
+    /// Properties added to the automatic style of the table
+    pub(crate) fn set_table_properties(&self, properties: &mut StyleItem) {
+        properties.set("fo:margin-top", "0.2cm");
+        properties.set("fo:margin-bottom", "0.2cm");
+        if *self == TablePreset::Grid {
+            properties.set("table:border-model", "collapsing");
+        }
+    }
+
+    /// Name of the style of the rows of this kind
+    pub(crate) fn row_style_name(&self, kind: RowKind) -> Option<String> {
+        (kind == RowKind::Header).then(|| format!("{}_HeaderRow", self.prefix()))
+    }
+
+    /// Name of the style of the cells of the rows of this kind
+    pub(crate) fn cell_style_name(&self, kind: RowKind) -> String {
+        let suffix = match (self, kind) {
+            (_, RowKind::Header) => "HeaderCell",
+            (TablePreset::Grid, _) => "Cell",
+            (TablePreset::Booktabs, RowKind::Body { last: true, .. }) => "LastCell",
+            (TablePreset::Booktabs, _) => "Cell",
+            (TablePreset::Striped, RowKind::Body { index, .. }) if index % 2 == 1 => "EvenCell",
+            (TablePreset::Striped, _) => "OddCell",
+        };
+        format!("{}_{}", self.prefix(), suffix)
+    }
+
+    /// Automatic row and cell styles of the preset
+    pub(crate) fn styles(&self) -> Vec<Style> {
+        let header_row = [("fo:keep-together", "always")];
+        let padding = ("fo:padding", "0.1cm");
+        let cells: Vec<(&str, Vec<(&str, &str)>)> = match self {
+            TablePreset::Grid => vec![
+                ("HeaderCell", vec![padding, ("fo:border", THIN_RULE), ("fo:background-color", HEADER_BACKGROUND)]),
+                ("Cell", vec![padding, ("fo:border", THIN_RULE)]),
+            ],
+            TablePreset::Booktabs => vec![
+                ("HeaderCell", vec![padding, ("fo:border-top", THICK_RULE), ("fo:border-bottom", THIN_RULE)]),
+                ("Cell", vec![padding, ("fo:border", "none")]),
+                ("LastCell", vec![padding, ("fo:border-bottom", THICK_RULE)]),
+            ],
+            TablePreset::Striped => vec![
+                ("HeaderCell", vec![padding, ("fo:border-bottom", THIN_RULE), ("fo:background-color", HEADER_BACKGROUND)]),
+                ("OddCell", vec![padding, ("fo:background-color", "transparent")]),
+                ("EvenCell", vec![padding, ("fo:background-color", STRIPE_BACKGROUND)]),
+            ],
+        };
+        let mut styles = vec![self.style(
+            "HeaderRow", StyleFamily::TableRow, StylePropertyGroup::TableRowProperties, &header_row
+        )];
+        for (suffix, properties) in cells {
+            styles.push(self.style(suffix, StyleFamily::TableCell, StylePropertyGroup::TableCellProperties, &properties));
+        }
+        styles
+    }
+
+    fn style(&self, suffix: &str, family: StyleFamily, group: StylePropertyGroup, properties: &[(&str, &str)]) -> Style {
+        let mut style = Style::new(format!("{}_{}", self.prefix(), suffix), family);
+        style.automatic = true;
+        let mut item = StyleItem::default();
+        for (key, value) in properties {
+            item.set(key, value);
+        }
+        style.properties.insert(group, item);
+        style
+    }
+}
@@ -1,14 +1,21 @@
 use std::io::Write;
 use xml::EventWriter;
 use xml::writer::{XmlEvent as XmlWriterEvent};
+use crate::error::Result;
 use crate::fodt_xml_write::FlatOdtXmlWrite;
+use crate::statistics::StatisticsWalker;
 
 pub struct Text {
     pub text: String
 }
 
 impl FlatOdtXmlWrite for Text {
-    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> xml::writer::Result<()> {
-        writer.write(XmlWriterEvent::characters(&self.text))
+    fn write_flat_odt_xml(&self, writer: &mut EventWriter<&mut dyn Write>) -> Result<()> {
+        writer.write(XmlWriterEvent::characters(&self.text))?;
+        Ok(())
+    }
+
+    fn update_statistics(&self, statistics: &mut StatisticsWalker) {
+        statistics.add_text(&self.text);
     }
 }